@@ -1,5 +1,27 @@
-include!(concat!(env!("OUT_DIR"), "/score.rs"));
+mod results {
+    include!(concat!(env!("OUT_DIR"), "/results.rs"));
+}
 
 fn main() {
-    println!("{SCORE}");
+    println!("{}", results::SCORE);
+    println!(
+        "{} lines, {} perfect clears, level {}, {} pieces in {:.1}s ({} mode, seed {})",
+        results::LINES,
+        results::PERFECT_CLEARS,
+        results::LEVEL,
+        results::PIECES,
+        results::DURATION.as_secs_f32(),
+        results::MODE,
+        results::SEED,
+    );
+    for row in results::BOARD {
+        println!("{row}");
+    }
+
+    #[cfg(tetris_tetris_cleared)]
+    println!("You cleared a Tetris while this binary was being built!");
+    #[cfg(tetris_perfect_clear)]
+    println!("You emptied the whole grid while this binary was being built!");
+    #[cfg(not(tetris_score_over_10))]
+    println!("Score over 10 points to unlock more features next time.");
 }
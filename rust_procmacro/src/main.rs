@@ -1,4 +1,4 @@
 fn main() {
-    let score = tetris_macro::run!();
+    let score = tetris_macro::run!().score;
     println!("{score}");
 }
@@ -0,0 +1,256 @@
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use tetris_core::{ParseSettingError, Settings};
+
+const ARGUMENTS: [&str; 17] = [
+    "mode",
+    "seed",
+    "min_score",
+    "timeout",
+    "randomizer",
+    "rotation",
+    "scoring",
+    "level_curve",
+    "lines_per_level",
+    "player",
+    "pieces",
+    "das",
+    "arr",
+    "soft_drop_factor",
+    "lock_delay",
+    "lock_reset",
+    "lock_resets",
+];
+
+/// Everything the arguments of `run!` ask for.
+#[derive(Default)]
+pub(crate) struct Args {
+    pub(crate) settings: Settings,
+    /// The score below which compilation fails, with the span of its value.
+    pub(crate) min_score: Option<(u32, Span)>,
+}
+
+/// A malformed macro argument, reported as a `compile_error!` pointing at the culprit.
+pub(crate) struct Error {
+    span: Span,
+    message: String,
+}
+
+impl Error {
+    pub(crate) fn new(span: Span, message: impl Into<String>) -> Error {
+        Error {
+            span,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn to_compile_error(&self) -> TokenStream {
+        let mut bang = Punct::new('!', Spacing::Alone);
+        bang.set_span(self.span);
+        let mut message = Literal::string(&self.message);
+        message.set_span(self.span);
+        let mut args = Group::new(Delimiter::Parenthesis, TokenTree::from(message).into());
+        args.set_span(self.span);
+        TokenStream::from_iter([
+            TokenTree::from(Ident::new("compile_error", self.span)),
+            TokenTree::from(bang),
+            TokenTree::from(args),
+        ])
+    }
+}
+
+/// Parses a comma-separated list of `key = value` arguments, such as
+/// `mode = sprint, seed = 1234, min_score = 50, timeout = 300`.
+///
+/// The player defaults to the `TETRIS_PLAYER` environment variable, if set.
+pub(crate) fn parse(input: TokenStream) -> Result<Args, Error> {
+    let mut args = Args::default();
+    let settings = &mut args.settings;
+    if let Ok(player) = env::var("TETRIS_PLAYER") {
+        settings.player = player.parse().map_err(|err| {
+            let message = format!("unknown value `{player}` in `TETRIS_PLAYER`, {err}");
+            Error::new(Span::call_site(), message)
+        })?;
+    }
+    let mut seen: Vec<String> = Vec::new();
+    let mut tokens = input.into_iter();
+    while let Some(token) = tokens.next() {
+        let TokenTree::Ident(key) = token else {
+            return Err(Error::new(
+                token.span(),
+                "expected an argument name, such as `seed`",
+            ));
+        };
+        let name = key.to_string();
+        let eq = match tokens.next() {
+            Some(TokenTree::Punct(eq)) if eq.as_char() == '=' => eq,
+            Some(other) => {
+                let message = format!("expected `=` after `{name}`");
+                return Err(Error::new(other.span(), message));
+            }
+            None => {
+                return Err(Error::new(
+                    key.span(),
+                    format!("expected `=` after `{name}`"),
+                ))
+            }
+        };
+        let Some(value) = tokens.next().map(unwrap_invisible_group) else {
+            return Err(Error::new(
+                eq.span(),
+                format!("expected a value for `{name}`"),
+            ));
+        };
+        if seen.contains(&name) {
+            let message = format!("`{name}` is given more than once");
+            return Err(Error::new(key.span(), message));
+        }
+
+        match name.as_str() {
+            "mode" => settings.mode = parse_name(&value)?,
+            "seed" => settings.seed = Some(parse_integer(&value)?),
+            "min_score" => {
+                let min_score = parse_u32(&value, "the minimum score")?;
+                args.min_score = Some((min_score, value.span()));
+            }
+            "timeout" => settings.timeout = Some(Duration::from_secs(parse_integer(&value)?)),
+            "randomizer" => settings.randomizer = parse_name(&value)?,
+            "rotation" => settings.rotation_system = parse_name(&value)?,
+            "scoring" => settings.scoring = parse_name(&value)?,
+            "level_curve" => settings.leveling.curve = parse_name(&value)?,
+            "lines_per_level" => {
+                settings.leveling.lines_per_level = parse_u32(&value, "the lines per level")?
+            }
+            "player" => settings.player = parse_name(&value)?,
+            "pieces" => settings.piece_limit = Some(parse_u32(&value, "the piece limit")?),
+            "das" => settings.handling.das = parse_u32(&value, "the DAS")?,
+            "arr" => settings.handling.arr = parse_u32(&value, "the ARR")?,
+            "soft_drop_factor" => {
+                settings.handling.soft_drop_factor = parse_u32(&value, "the soft drop factor")?
+            }
+            "lock_delay" => settings.lock_delay.frames = parse_u32(&value, "the lock delay")?,
+            "lock_reset" => settings.lock_delay.reset = parse_name(&value)?,
+            "lock_resets" => {
+                settings.lock_delay.max_resets = parse_u32(&value, "the number of lock resets")?
+            }
+            _ => {
+                let expected = ARGUMENTS.map(|arg| format!("`{arg}`")).join(", ");
+                let message = format!("unknown argument `{name}`, expected one of {expected}");
+                return Err(Error::new(key.span(), message));
+            }
+        }
+        seen.push(name);
+
+        match tokens.next() {
+            None => break,
+            Some(TokenTree::Punct(comma)) if comma.as_char() == ',' => {}
+            Some(other) => {
+                return Err(Error::new(other.span(), "expected `,` between arguments"));
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Parses the single string literal given to `include_replay!`, returning its value and span.
+pub(crate) fn parse_path(input: TokenStream) -> Result<(String, Span), Error> {
+    let mut tokens = input.into_iter().map(unwrap_invisible_group);
+    let expected = "expected the path of a replay file, such as `\"replays/best.ttr\"`";
+    let literal = match tokens.next() {
+        Some(TokenTree::Literal(literal)) => literal,
+        Some(other) => return Err(Error::new(other.span(), expected)),
+        None => return Err(Error::new(Span::call_site(), expected)),
+    };
+    if let Some(extra) = tokens.next() {
+        return Err(Error::new(extra.span(), "expected a single path"));
+    }
+    let path =
+        parse_string(&literal.to_string()).ok_or_else(|| Error::new(literal.span(), expected))?;
+    Ok((path, literal.span()))
+}
+
+/// Reads the value of a string literal, raw or not.
+fn parse_string(literal: &str) -> Option<String> {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let delimiter = &raw[..hashes];
+        return raw
+            .strip_prefix(delimiter)?
+            .strip_prefix('"')?
+            .strip_suffix(delimiter)?
+            .strip_suffix('"')
+            .map(str::to_owned);
+    }
+    let mut chars = literal.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            'r' => value.push('\r'),
+            '0' => value.push('\0'),
+            c @ ('\\' | '"' | '\'') => value.push(c),
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+/// Sees through the invisible groups `macro_rules!` wraps around `$value:expr` fragments.
+fn unwrap_invisible_group(token: TokenTree) -> TokenTree {
+    if let TokenTree::Group(group) = &token {
+        if group.delimiter() == Delimiter::None {
+            let mut inner = group.stream().into_iter();
+            if let (Some(value), None) = (inner.next(), inner.next()) {
+                return unwrap_invisible_group(value);
+            }
+        }
+    }
+    token
+}
+
+fn parse_name<T: FromStr<Err = ParseSettingError>>(value: &TokenTree) -> Result<T, Error> {
+    let TokenTree::Ident(ident) = value else {
+        return Err(Error::new(
+            value.span(),
+            "expected a name, such as `sprint`",
+        ));
+    };
+    ident
+        .to_string()
+        .parse()
+        .map_err(|err| Error::new(ident.span(), format!("unknown value `{ident}`, {err}")))
+}
+
+fn parse_integer(value: &TokenTree) -> Result<u64, Error> {
+    let error = || Error::new(value.span(), "expected an unsigned integer literal");
+    let TokenTree::Literal(literal) = value else {
+        return Err(error());
+    };
+    let text = literal.to_string().replace('_', "");
+    let (digits, radix) = match text.get(..2) {
+        Some("0x") => (&text[2..], 16),
+        Some("0o") => (&text[2..], 8),
+        Some("0b") => (&text[2..], 2),
+        _ => (&text[..], 10),
+    };
+    let digits = ["u8", "u16", "u32", "u64", "u128", "usize"]
+        .iter()
+        .find_map(|suffix| digits.strip_suffix(suffix))
+        .unwrap_or(digits);
+    u64::from_str_radix(digits, radix).map_err(|_| error())
+}
+
+/// Parses an integer that has to fit in a `u32`, naming it as `what` when it does not.
+fn parse_u32(value: &TokenTree, what: &str) -> Result<u32, Error> {
+    u32::try_from(parse_integer(value)?)
+        .map_err(|_| Error::new(value.span(), format!("{what} is out of range")))
+}
@@ -0,0 +1,52 @@
+use std::env;
+
+use proc_macro::Span;
+
+/// Who is expanding the macro, which decides whether a new game may start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Expansion {
+    /// rust-analyzer or another IDE, which expands macros over and over while typing.
+    Ide,
+    /// `cargo check` or `cargo clippy`, which only emit metadata.
+    Check,
+    /// A build that produces a binary.
+    Build,
+}
+
+impl Expansion {
+    pub(crate) fn current() -> Expansion {
+        let in_rust_analyzer = env::var_os("RUST_ANALYZER_INTERNALS_DO_NOT_USE").is_some()
+            || env::current_exe().is_ok_and(|exe| {
+                exe.file_name()
+                    .is_some_and(|name| name.to_string_lossy().contains("rust-analyzer"))
+            });
+        if in_rust_analyzer {
+            return Expansion::Ide;
+        }
+
+        // Proc macros run inside rustc, whose `--emit` flag only asks for `link` when the crate
+        // is actually built.
+        let emits_link = env::args().any(|arg| {
+            arg.strip_prefix("--emit=")
+                .is_some_and(|kinds| kinds.split(',').any(|kind| kind.starts_with("link")))
+        });
+        if emits_link {
+            Expansion::Build
+        } else {
+            Expansion::Check
+        }
+    }
+}
+
+/// Identifies one invocation of the macro: the crate, the call site and the arguments, so that
+/// moving the call or changing its arguments invalidates the cached result.
+pub(crate) fn cache_key(args: &str) -> String {
+    let call_site = Span::call_site();
+    format!(
+        "{}@{}:{}:{} ({args})",
+        env::var("CARGO_CRATE_NAME").unwrap_or_default(),
+        call_site.file(),
+        call_site.line(),
+        call_site.column(),
+    )
+}
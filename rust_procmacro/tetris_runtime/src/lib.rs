@@ -0,0 +1,67 @@
+//! Types that `tetris_macro::run!` expands to.
+//!
+//! A procedural macro crate cannot export anything but macros, so the result type lives here.
+//! Crates that call `run!` depend on both crates:
+//!
+//! ```ignore
+//! let result = tetris_macro::run!(seed = 1234);
+//! println!("{} points in {:?}", result.score, result.duration);
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+/// Number of columns of [`TetrisResult::board`].
+pub const BOARD_WIDTH: usize = 10;
+/// Number of rows of [`TetrisResult::board`].
+pub const BOARD_HEIGHT: usize = 22;
+
+/// The outcome of the Tetris session played while the crate was compiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TetrisResult {
+    pub score: u32,
+    pub lines: u32,
+    /// How many times the whole grid was emptied, something to brag about.
+    pub perfect_clears: u32,
+    pub level: u32,
+    /// Pieces locked into the grid.
+    pub pieces: u32,
+    /// Time spent playing.
+    pub duration: Duration,
+    /// Seed of the piece sequence. Pass it to `run!(seed = ...)` to play the same pieces again.
+    pub seed: u64,
+    /// The final stack, row by row from the top, with `.` for empty cells and the letter of the
+    /// piece for filled ones.
+    pub board: [&'static str; BOARD_HEIGHT],
+}
+
+impl From<TetrisResult> for u32 {
+    fn from(result: TetrisResult) -> u32 {
+        result.score
+    }
+}
+
+impl fmt::Display for TetrisResult {
+    /// Formats the statistics on one line, followed by the final stack when `{:#}` is used.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "score {}, {} lines, level {}, {} pieces in {:.1}s (seed {})",
+            self.score,
+            self.lines,
+            self.level,
+            self.pieces,
+            self.duration.as_secs_f32(),
+            self.seed,
+        )?;
+        if self.perfect_clears > 0 {
+            write!(f, ", {} perfect clears", self.perfect_clears)?;
+        }
+        if f.alternate() {
+            for row in self.board {
+                write!(f, "\n{row}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+//! Serves the JSON-lines protocol of [`tetris_core::Gym`] to external agents.
+//!
+//! ```text
+//! tetris_gym                  # over stdin and stdout
+//! tetris_gym --socket <path>  # over a Unix socket
+//! ```
+
+use std::io;
+use std::process::ExitCode;
+
+use tetris_core::{Gym, Settings};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => Gym::new(Settings::default()).serve(io::stdin().lock(), io::stdout().lock()),
+        #[cfg(unix)]
+        ["--socket", path] => Gym::serve_unix(Settings::default(), path),
+        _ => {
+            eprintln!("usage: tetris_gym [--socket <path>]");
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    run_headless, Action, Controller, Game, Grid, InputFrame, Replay, Rotation, Settings, State,
+    Summary, Tetromino,
+};
+
+/// A heuristic player, so that builds can finish without anyone at the keyboard.
+///
+/// For every new piece, the bot tries each rotation and column, scores the resulting stack
+/// with the weights of El-Tetris (landing height, cleared rows, row and column transitions,
+/// holes and wells) and steers the piece to the best spot. As a [`Controller`], it plays
+/// through [`InputFrame`]s like a human would, so its games can be recorded and replayed as any
+/// other.
+#[derive(Debug, Clone, Default)]
+pub struct Bot {
+    /// The spot picked for the falling piece, along with the number of pieces locked before it.
+    plan: Option<(u32, Placement)>,
+    /// Whether the last input held a shift key, which has to be released before the next tap.
+    shifting: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Placement {
+    rot: Rotation,
+    x: i8,
+}
+
+impl Bot {
+    /// Pieces after which [`run_bot`] stops when the settings set no limit, as a good game
+    /// could otherwise go on for hours.
+    pub const DEFAULT_PIECE_LIMIT: u32 = 1000;
+
+    // The weights found by Islam El-Ashi for El-Tetris.
+    const LANDING_HEIGHT: f64 = -4.500158825082766;
+    const ROWS_ELIMINATED: f64 = 3.4181268101392694;
+    const ROW_TRANSITIONS: f64 = -3.2178882868487753;
+    const COLUMN_TRANSITIONS: f64 = -9.348695305445199;
+    const HOLES: f64 = -7.899265427351652;
+    const WELL_SUMS: f64 = -3.3855972247263626;
+
+    /// Creates a bot that has not planned anything yet.
+    pub fn new() -> Bot {
+        Bot::default()
+    }
+
+    fn next_move(&mut self, game: &Game) -> Action {
+        let placement = match self.plan {
+            Some((pieces, placement)) if pieces == game.pieces() => placement,
+            _ => {
+                let placement = Bot::best_placement(game);
+                self.plan = Some((game.pieces(), placement));
+                placement
+            }
+        };
+
+        if game.rot != placement.rot {
+            let (action, rot) = if game.rot.spin_acw() == placement.rot {
+                (Action::RotateCcw, game.rot.spin_acw())
+            } else {
+                (Action::RotateCw, game.rot.spin_cw())
+            };
+            let kicks = game
+                .settings()
+                .rotation_system
+                .kicks(game.tetromino, game.rot, rot);
+            if kicks
+                .iter()
+                .any(|&(dx, dy)| game._movable_with(rot, dx, dy))
+            {
+                return action;
+            }
+        } else if game.pos.0 != placement.x {
+            let (action, dx) = if game.pos.0 < placement.x {
+                (Action::Right, 1)
+            } else {
+                (Action::Left, -1)
+            };
+            if game._movable_with(game.rot, dx, 0) {
+                return action;
+            }
+        }
+        // the piece is in place, or cannot get any closer
+        Action::HardDrop
+    }
+
+    /// Tries every rotation and every column the piece can slide to from where it is.
+    fn best_placement(game: &Game) -> Placement {
+        let (x_from, y) = game.pos;
+        let mut best = Placement {
+            rot: game.rot,
+            x: x_from,
+        };
+        let mut best_score = f64::NEG_INFINITY;
+        for rot in [
+            Rotation::DEG0,
+            Rotation::DEG90,
+            Rotation::DEG180,
+            Rotation::DEG270,
+        ] {
+            let fits = |x| fits(game.grid(), game.tetromino, rot, (x, y));
+            if !fits(x_from) {
+                continue;
+            }
+            let columns = -2..Grid::WIDTH as i8 + 2;
+            let reachable_left = columns.clone().rev().skip_while(|&x| x > x_from);
+            let reachable_right = columns.skip_while(|&x| x < x_from);
+            let reachable = reachable_left
+                .take_while(|&x| fits(x))
+                .chain(reachable_right.skip(1).take_while(|&x| fits(x)));
+            for x in reachable {
+                let score = Bot::evaluate(game.grid(), game.tetromino, rot, (x, y));
+                if score > best_score {
+                    best_score = score;
+                    best = Placement { rot, x };
+                }
+            }
+        }
+        best
+    }
+
+    /// Drops the piece from `pos` and scores the stack it leaves.
+    fn evaluate(grid: &Grid, tetromino: Tetromino, rot: Rotation, pos: (i8, i8)) -> f64 {
+        let (x, mut y) = pos;
+        while fits(grid, tetromino, rot, (x, y + 1)) {
+            y += 1;
+        }
+        let mut grid = grid.clone();
+        let cells = tetromino
+            .neighbors(rot)
+            .map(|(dx, dy)| ((x + dx) as u8, (y + dy) as u8));
+        for (x, y) in cells {
+            *grid.at_mut(x, y) = Some(tetromino);
+        }
+        let rows_eliminated = grid.squash_filled_rows().count();
+
+        let top = cells.iter().map(|&(_, y)| y).min().unwrap();
+        let bottom = cells.iter().map(|&(_, y)| y).max().unwrap();
+        let landing_height = f64::from(Grid::HEIGHT) - f64::from(top + bottom) / 2.;
+
+        let filled = |x: i8, y: i8| {
+            !(0..Grid::WIDTH as i8).contains(&x)
+                || y >= Grid::HEIGHT as i8
+                || grid.at(x as u8, y as u8).is_some()
+        };
+        let (mut row_transitions, mut column_transitions, mut holes, mut well_sums) = (0, 0, 0, 0);
+        for y in 0..Grid::HEIGHT as i8 {
+            for x in 0..=Grid::WIDTH as i8 {
+                // the walls count as filled
+                if filled(x - 1, y) != filled(x, y) {
+                    row_transitions += 1;
+                }
+            }
+        }
+        for x in 0..Grid::WIDTH as i8 {
+            let mut covered = false;
+            let mut well_depth = 0;
+            for y in 0..Grid::HEIGHT as i8 {
+                // the floor counts as filled
+                if filled(x, y) != filled(x, y + 1) {
+                    column_transitions += 1;
+                }
+                if filled(x, y) {
+                    covered = true;
+                    well_depth = 0;
+                    continue;
+                }
+                if covered {
+                    holes += 1;
+                }
+                if filled(x - 1, y) && filled(x + 1, y) {
+                    well_depth += 1;
+                    well_sums += well_depth;
+                } else {
+                    well_depth = 0;
+                }
+            }
+        }
+
+        Bot::LANDING_HEIGHT * landing_height
+            + Bot::ROWS_ELIMINATED * f64::from(rows_eliminated)
+            + Bot::ROW_TRANSITIONS * f64::from(row_transitions)
+            + Bot::COLUMN_TRANSITIONS * f64::from(column_transitions)
+            + Bot::HOLES * f64::from(holes)
+            + Bot::WELL_SUMS * f64::from(well_sums)
+    }
+}
+
+impl Controller for Bot {
+    /// Starts the game right away, resumes it when paused and quits once it is over.
+    fn input(&mut self, game: &Game) -> Option<InputFrame> {
+        let action = match game.state {
+            State::Start | State::Pause => Action::Confirm,
+            State::Over => Action::Quit,
+            State::Play => {
+                let action = self.next_move(game);
+                // shift keys are tapped rather than held, so that auto repeat never overshoots
+                let shift = matches!(action, Action::Left | Action::Right);
+                if shift && self.shifting {
+                    self.shifting = false;
+                    return Some(InputFrame::IDLE);
+                }
+                self.shifting = shift;
+                action
+            }
+            State::WindowClose => return None,
+        };
+        Some(InputFrame::press(action))
+    }
+}
+
+/// Whether `tetromino` fits at `pos` on `grid`.
+fn fits(grid: &Grid, tetromino: Tetromino, rot: Rotation, pos: (i8, i8)) -> bool {
+    tetromino.neighbors(rot).iter().all(|&(dx, dy)| {
+        let (x, y) = (pos.0 + dx, pos.1 + dy);
+        (0..Grid::WIDTH as i8).contains(&x)
+            && (0..Grid::HEIGHT as i8).contains(&y)
+            && grid.at(x as u8, y as u8).is_none()
+    })
+}
+
+/// Lets the [`Bot`] play a game with `settings` without opening a window, and returns its
+/// summary and replay.
+///
+/// Same as [`run_headless`] with a new [`Bot`], except that without any limit in `settings`, the
+/// bot stops after [`Bot::DEFAULT_PIECE_LIMIT`] pieces.
+pub fn run_bot(settings: Settings) -> (Summary, Replay) {
+    let mut settings = settings;
+    if settings.piece_limit.is_none() && settings.timeout.is_none() {
+        settings.piece_limit = Some(Bot::DEFAULT_PIECE_LIMIT);
+    }
+    run_headless(settings, Bot::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::with_piece;
+    use crate::Player;
+
+    fn settings() -> Settings {
+        Settings {
+            seed: Some(7),
+            player: Player::Bot,
+            piece_limit: Some(200),
+            ..Settings::DEFAULT
+        }
+    }
+
+    #[test]
+    fn plays_the_same_game_for_a_seed() {
+        let (summary, replay) = run_bot(settings());
+        assert_eq!(summary.pieces, 200);
+        assert!(summary.lines > 0);
+        assert_eq!(run_bot(settings()).0, summary);
+        assert_eq!(replay.play().score(), summary.score);
+    }
+
+    #[test]
+    fn drops_an_i_piece_down_the_well() {
+        // four rows filled but for the rightmost column
+        let mut filled = Vec::new();
+        for y in Grid::HEIGHT - 4..Grid::HEIGHT {
+            filled.extend((0..Grid::WIDTH - 1).map(|x| (x, y)));
+        }
+        let game = with_piece(Tetromino::I, (4, 1), Rotation::DEG0, &filled);
+
+        let placement = Bot::best_placement(&game);
+        let columns = Tetromino::I
+            .neighbors(placement.rot)
+            .map(|(dx, _)| placement.x + dx);
+        assert_eq!(columns, [Grid::WIDTH as i8 - 1; 4]);
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+use crate::Summary;
+
+/// The directory Cargo builds into: `CARGO_TARGET_DIR` if set, otherwise the `target`
+/// directory next to the `Cargo.lock` of the workspace being compiled.
+///
+/// Works from build scripts and procedural macros alike, since Cargo sets
+/// `CARGO_MANIFEST_DIR` for both.
+pub fn target_dir() -> PathBuf {
+    if let Some(target_dir) = env::var_os("CARGO_TARGET_DIR") {
+        return PathBuf::from(target_dir);
+    }
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let workspace_root = manifest_dir
+        .ancestors()
+        .find(|dir| dir.join("Cargo.lock").is_file())
+        .unwrap_or(&manifest_dir);
+    workspace_root.join("target")
+}
+
+/// The directory this crate keeps its files in, `<target>/tetris`.
+pub fn tetris_dir() -> PathBuf {
+    target_dir().join("tetris")
+}
+
+/// The result of the last session played for a given key, kept under `<target>/tetris/cache`.
+///
+/// Callers pick a key that changes whenever the result should no longer be reused, for
+/// instance the crate, the call site and the settings of the session.
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    key: String,
+    path: PathBuf,
+}
+
+impl ResultCache {
+    /// The cache entry for `key`.
+    pub fn new(key: &str) -> ResultCache {
+        let key = key.replace('\n', " ");
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let file_name = format!("{:016x}.txt", hasher.finish());
+        ResultCache {
+            key,
+            path: tetris_dir().join("cache").join(file_name),
+        }
+    }
+
+    /// Where the entry is stored. Deleting the file invalidates it.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The stored result, if there is one for this very key.
+    pub fn load(&self) -> Option<Summary> {
+        let text = fs::read_to_string(&self.path).ok()?;
+        let (key, summary) = text.split_once('\n')?;
+        if key.strip_prefix("key=")? != self.key {
+            return None;
+        }
+        Summary::decode(summary)
+    }
+
+    /// Replaces the stored result with `summary`.
+    pub fn store(&self, summary: &Summary) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(
+            &self.path,
+            format!("key={}\n{}", self.key, summary.encode()),
+        )
+    }
+}
@@ -0,0 +1,95 @@
+use std::vec;
+
+use crate::{Game, InputFrame, Replay, Settings, State, Summary};
+
+/// Decides what the player does on each frame of a game.
+///
+/// The keyboard of the window, the playback of a [`Replay`] and the [`Bot`](crate::Bot) are all
+/// controllers, and so can be any agent written against the public state of [`Game`]:
+///
+/// ```
+/// use tetris_core::{Action, Controller, Game, InputFrame, Settings, State};
+///
+/// /// Drops every piece where it spawns.
+/// struct Dropper;
+///
+/// impl Controller for Dropper {
+///     fn input(&mut self, game: &Game) -> Option<InputFrame> {
+///         match game.state {
+///             State::Start => Some(InputFrame::press(Action::Confirm)),
+///             State::Play => Some(InputFrame::press(Action::HardDrop)),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let settings = Settings { seed: Some(1234), ..Settings::default() };
+/// let (summary, _) = tetris_core::run_headless(settings, &mut Dropper);
+/// assert_eq!(summary.lines, 0);
+/// ```
+pub trait Controller {
+    /// Returns the input of the next frame of `game`, or `None` to end the session.
+    fn input(&mut self, game: &Game) -> Option<InputFrame>;
+}
+
+impl<C: Controller + ?Sized> Controller for &mut C {
+    fn input(&mut self, game: &Game) -> Option<InputFrame> {
+        (**self).input(game)
+    }
+}
+
+impl<C: Controller + ?Sized> Controller for Box<C> {
+    fn input(&mut self, game: &Game) -> Option<InputFrame> {
+        (**self).input(game)
+    }
+}
+
+/// Plays back the inputs of a [`Replay`], one per frame, then ends the session.
+#[derive(Debug, Clone)]
+pub struct Playback {
+    inputs: vec::IntoIter<InputFrame>,
+}
+
+impl Playback {
+    /// Plays back `replay`, which should be played with its own settings.
+    pub fn new(replay: Replay) -> Playback {
+        Playback {
+            inputs: replay.inputs.into_iter(),
+        }
+    }
+}
+
+impl Controller for Playback {
+    fn input(&mut self, _game: &Game) -> Option<InputFrame> {
+        self.inputs.next()
+    }
+}
+
+/// Lets `controller` play a session with `settings` without opening a window, and returns the
+/// summary and replay of its last game.
+///
+/// The session ends when the controller returns `None`, the window would close, or a limit of
+/// `settings` is reached. The `timeout` counts game time rather than wall-clock time, so the same
+/// settings and controller always give the same result.
+pub fn run_headless(settings: Settings, mut controller: impl Controller) -> (Summary, Replay) {
+    let frame_limit = settings
+        .timeout
+        .map(|timeout| timeout.as_millis() * u128::from(Summary::FRAME_RATE) / 1000);
+
+    let mut game = Game::with_settings(settings);
+    let mut replay = Replay::new(&game);
+    while game.state != State::WindowClose {
+        if settings
+            .piece_limit
+            .is_some_and(|limit| game.pieces() >= limit)
+            || frame_limit.is_some_and(|limit| u128::from(game.frames()) >= limit)
+        {
+            break;
+        }
+        let Some(input) = controller.input(&game) else {
+            break;
+        };
+        replay.update(&mut game, input);
+    }
+    (Summary::of(&game), replay)
+}
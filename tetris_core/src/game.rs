@@ -0,0 +1,946 @@
+use crate::handling::KeyRepeat;
+use crate::input::ActionQueue;
+use crate::randomizer::AnyRandomizer;
+use crate::scoring::AnyScoring;
+use crate::{
+    Action, Grid, InputFrame, Level, LevelCurve, LockEvent, LockReset, Mode, Rotation,
+    RotationSystem, Settings, SplitMix64, TSpin, Tetromino,
+};
+
+/// A single Tetris session: the playfield, the falling piece and the score.
+pub struct Game {
+    /// Which screen the game is currently on.
+    pub state: State,
+    settings: Settings,
+    seed: u64,
+    rng: SplitMix64,
+    randomizer: AnyRandomizer,
+    scoring: AnyScoring,
+    pub(crate) grid: Grid,
+    pub(crate) pos: (i8, i8),
+    pub(crate) tetromino: Tetromino,
+    pub(crate) rot: Rotation,
+    pub(crate) holding_tetromino: Option<Tetromino>,
+    pub(crate) swapped: bool,
+    pub(crate) next_tetromino: Tetromino,
+    pub(crate) level: Level,
+    pub(crate) tick: u32,
+    /// How far the falling piece dropped towards the next row, in [`Level::CELL`]s.
+    fall: u32,
+    lock_tick: u32,
+    lock_resets: u32,
+    lowest_row: i8,
+    soft_drop_rows: u32,
+    /// The kick the falling piece last turned with, unless it moved since.
+    last_kick: Option<u8>,
+    pub(crate) score: u32,
+    lines: u32,
+    line_clears: [u32; 4],
+    perfect_clears: u32,
+    pieces: u32,
+    frames: u32,
+    repeat: KeyRepeat,
+}
+
+/// What changed during a call to [`Game::update`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// The falling piece was shifted, rotated or moved down.
+    pub moved: bool,
+    /// The falling piece was locked into the grid.
+    pub locked: bool,
+    /// Number of rows cleared by the locked piece.
+    pub lines_cleared: u8,
+    /// The T-spin made by the locked piece, if any.
+    pub t_spin: Option<TSpin>,
+    /// The locked piece emptied the whole grid.
+    pub perfect_clear: bool,
+    /// The falling piece was swapped with the hold slot.
+    pub held: bool,
+    /// The state the game switched to, if it did.
+    pub state: Option<State>,
+}
+
+/// The screens a [`Game`] moves through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum State {
+    #[default]
+    Start,
+    Play,
+    Pause,
+    Over,
+    WindowClose,
+}
+
+impl Game {
+    /// Where pieces appear: centred, rounding to the left, with their top row at the top.
+    const SPAWN_POS: (i8, i8) = (Grid::WIDTH as i8 / 2 - 1, 1);
+
+    /// The order in which the actions of a single frame are applied: the piece is held first,
+    /// so that the new piece can be turned and shifted right away, and dropped last.
+    pub const ACTION_ORDER: [Action; 6] = [
+        Action::Hold,
+        Action::RotateCw,
+        Action::RotateCcw,
+        Action::Left,
+        Action::Right,
+        Action::HardDrop,
+    ];
+
+    /// Creates a game with the default [`Settings`], waiting on the start screen.
+    pub fn new() -> Self {
+        Game::with_settings(Settings::default())
+    }
+
+    /// Creates a game played with `settings`, waiting on the start screen.
+    ///
+    /// Without a seed in `settings`, a random one is picked; [`Game::seed`] tells which.
+    pub fn with_settings(settings: Settings) -> Self {
+        let seed = match settings.seed {
+            Some(seed) => seed,
+            None => rand::random(),
+        };
+        Game::with_seed(settings, seed)
+    }
+
+    /// Creates a game played with `settings` whose pieces are drawn from `seed`, waiting on the
+    /// start screen. The seed in `settings` is ignored.
+    ///
+    /// Unlike [`Game::with_settings`], this can be called in `const` code.
+    pub const fn with_seed(settings: Settings, seed: u64) -> Self {
+        let mut rng = SplitMix64::new(seed);
+        let mut randomizer = AnyRandomizer::new(settings.randomizer);
+        let tetromino = randomizer.deal(&mut rng);
+        let next_tetromino = randomizer.deal(&mut rng);
+        Game {
+            state: State::Start,
+            settings,
+            seed,
+            rng,
+            randomizer,
+            scoring: AnyScoring::new(settings.scoring),
+            grid: Grid::new(),
+            pos: Game::SPAWN_POS,
+            tetromino,
+            rot: Rotation::DEG0,
+            holding_tetromino: None,
+            swapped: false,
+            next_tetromino,
+            level: Level::with_leveling(settings.leveling),
+            tick: 0,
+            fall: 0,
+            lock_tick: 0,
+            lock_resets: 0,
+            lowest_row: Game::SPAWN_POS.1,
+            soft_drop_rows: 0,
+            last_kick: None,
+            score: 0,
+            lines: 0,
+            line_clears: [0; 4],
+            perfect_clears: 0,
+            pieces: 0,
+            frames: 0,
+            repeat: KeyRepeat::new(),
+        }
+    }
+
+    /// The points scored so far.
+    pub const fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// The rows cleared so far.
+    pub const fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    /// How many singles, doubles, triples and tetrises were cleared so far, in that order.
+    pub const fn line_clears(&self) -> [u32; 4] {
+        self.line_clears
+    }
+
+    /// How many times the whole grid was emptied so far.
+    pub const fn perfect_clears(&self) -> u32 {
+        self.perfect_clears
+    }
+
+    /// The pieces locked so far.
+    pub const fn pieces(&self) -> u32 {
+        self.pieces
+    }
+
+    /// The current level.
+    pub const fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// The frames spent in [`State::Play`] so far.
+    pub const fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// The playfield with every locked mino.
+    pub const fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// The rules this game is played with.
+    pub const fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// The seed the piece sequence of this game was drawn from.
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The falling piece.
+    pub const fn piece(&self) -> Tetromino {
+        self.tetromino
+    }
+
+    /// Where the falling piece is, as the column and row its minos are offset from by
+    /// [`Tetromino::neighbors`].
+    pub const fn position(&self) -> (i8, i8) {
+        self.pos
+    }
+
+    /// How the falling piece is turned.
+    pub const fn rotation(&self) -> Rotation {
+        self.rot
+    }
+
+    /// The piece in the hold slot, if any.
+    pub const fn held_piece(&self) -> Option<Tetromino> {
+        self.holding_tetromino
+    }
+
+    /// Whether the falling piece may still be swapped with the hold slot.
+    pub const fn can_hold(&self) -> bool {
+        !self.swapped
+    }
+
+    /// The piece that spawns after the falling one.
+    pub const fn next_piece(&self) -> Tetromino {
+        self.next_tetromino
+    }
+
+    /// Forgets how long the shift keys have been held, so that the next frame holding one
+    /// shifts the piece as a fresh press would.
+    pub(crate) const fn release_keys(&mut self) {
+        self.repeat = KeyRepeat::new();
+    }
+
+    pub(crate) const fn _movable_with(&self, rot: Rotation, x_dir: i8, y_dir: i8) -> bool {
+        let (x_from, y_from) = self.pos;
+        let neighbors = self.tetromino.neighbors(rot);
+        let mut i = 0;
+        while i < neighbors.len() {
+            let (dx, dy) = neighbors[i];
+            let x = x_from + dx + x_dir;
+            let y = y_from + dy + y_dir;
+            if x < 0 || x >= Grid::WIDTH as i8 || y < 0 || y >= Grid::HEIGHT as i8 {
+                return false;
+            }
+            if self.grid.at(x as u8, y as u8).is_some() {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Turns the falling piece to `new_rot`, trying each kick of the rotation system in order.
+    /// Returns whether the piece could turn at all.
+    const fn rotate(&mut self, new_rot: Rotation) -> bool {
+        let kicks = self
+            .settings
+            .rotation_system
+            .kicks(self.tetromino, self.rot, new_rot);
+        let mut i = 0;
+        while i < kicks.len() {
+            let (x_offset, y_offset) = kicks[i];
+            if self._movable_with(new_rot, x_offset, y_offset) {
+                self.pos.0 += x_offset;
+                self.pos.1 += y_offset;
+                self.rot = new_rot;
+                self.last_kick = Some(i as u8);
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+
+    /// Tells whether the falling piece, about to lock, makes a T-spin: it has to be a T whose
+    /// last move was a turn, with at least three of the four cells diagonal to its centre
+    /// filled. It is a mini T-spin unless both corners it points to are filled, or the turn
+    /// needed the last kick of SRS, as in the T-spin triple.
+    const fn t_spin(&self) -> Option<TSpin> {
+        // clockwise from the top left, so that a T turned `n` quarters points to corners `n`
+        // and `n + 1`
+        const CORNERS: [(i8, i8); 4] = [(-1, -1), (1, -1), (1, 1), (-1, 1)];
+        // the offset of the fifth test of the SRS kick tables of T pieces
+        const LAST_KICK: u8 = 4;
+
+        let Some(kick) = self.last_kick else {
+            return None;
+        };
+        if !matches!(self.tetromino, Tetromino::T) {
+            return None;
+        }
+        let mut filled = [false; 4];
+        let mut count = 0;
+        let mut i = 0;
+        while i < CORNERS.len() {
+            let x = self.pos.0 + CORNERS[i].0;
+            let y = self.pos.1 + CORNERS[i].1;
+            // the walls and the floor count as filled
+            filled[i] = x < 0
+                || x >= Grid::WIDTH as i8
+                || y < 0
+                || y >= Grid::HEIGHT as i8
+                || self.grid.at(x as u8, y as u8).is_some();
+            if filled[i] {
+                count += 1;
+            }
+            i += 1;
+        }
+        if count < 3 {
+            return None;
+        }
+        let front = self.rot as usize;
+        let upgraded =
+            matches!(self.settings.rotation_system, RotationSystem::Srs) && kick == LAST_KICK;
+        if (filled[front] && filled[(front + 1) % 4]) || upgraded {
+            Some(TSpin::Full)
+        } else {
+            Some(TSpin::Mini)
+        }
+    }
+
+    /// Restarts the lock delay after the falling piece moved or turned, as far as the
+    /// [`LockReset`] rule allows.
+    const fn reset_lock_delay(&mut self) {
+        let lock_delay = self.settings.lock_delay;
+        match lock_delay.reset {
+            LockReset::Infinity => self.lock_tick = 0,
+            LockReset::MoveReset => {
+                // only moves made while the delay runs count towards the limit
+                if self.lock_tick > 0 && self.lock_resets < lock_delay.max_resets {
+                    self.lock_tick = 0;
+                    self.lock_resets += 1;
+                }
+            }
+            LockReset::StepReset => {}
+        }
+    }
+
+    /// Advances the game by one frame according to the player's `input`.
+    pub const fn update(&mut self, input: InputFrame) -> Changes {
+        let mut changes = Changes {
+            moved: false,
+            locked: false,
+            lines_cleared: 0,
+            t_spin: None,
+            perfect_clear: false,
+            held: false,
+            state: None,
+        };
+        let prev_state = self.state;
+        self.step(input, &mut changes);
+        if self.state as u8 != prev_state as u8 {
+            changes.state = Some(self.state);
+        }
+        changes
+    }
+
+    const fn step(&mut self, input: InputFrame, changes: &mut Changes) {
+        match self.state {
+            State::Start => {
+                if input.pressed.contains(Action::Confirm) {
+                    self.state = State::Play;
+                } else if input.pressed.contains(Action::Quit) {
+                    self.state = State::WindowClose;
+                }
+            }
+            State::Play => {
+                if input.pressed.contains(Action::Pause) {
+                    self.state = State::Pause;
+                    return;
+                }
+
+                self.frames += 1;
+                if matches!(self.settings.mode, Mode::Ultra) && self.frames >= Mode::ULTRA_FRAMES {
+                    self.state = State::Over;
+                    return;
+                }
+
+                let (direction, cells) = self.repeat.shift(input, self.settings.handling);
+                let mut actions = input.pressed.without(Action::Left).without(Action::Right);
+                if cells > 0 {
+                    actions = actions.with(if direction < 0 {
+                        Action::Left
+                    } else {
+                        Action::Right
+                    });
+                }
+                let mut queue = ActionQueue::new(actions, &Game::ACTION_ORDER);
+                while let Some(action) = queue.pop() {
+                    match action {
+                        Action::Hold if !self.swapped => {
+                            if let Some(hold) = self.holding_tetromino {
+                                self.holding_tetromino = Some(self.tetromino);
+                                self.tetromino = hold;
+                                self.pos = Game::SPAWN_POS;
+                                self.rot = Rotation::DEG0;
+                            } else {
+                                self.holding_tetromino = Some(self.tetromino);
+                                reset_piece(self);
+                                self.pos = Game::SPAWN_POS;
+                            }
+                            start_falling(self);
+                            self.swapped = true;
+                            changes.held = true;
+                        }
+                        Action::RotateCw => changes.moved |= self.rotate(self.rot.spin_cw()),
+                        Action::RotateCcw => changes.moved |= self.rotate(self.rot.spin_acw()),
+                        Action::Left | Action::Right => {
+                            let mut shifted = 0;
+                            while shifted < cells && self._movable_with(self.rot, direction, 0) {
+                                self.pos.0 += direction;
+                                shifted += 1;
+                            }
+                            if shifted > 0 {
+                                self.last_kick = None;
+                                changes.moved = true;
+                            }
+                        }
+                        Action::HardDrop => {
+                            let mut rows = 0;
+                            while self._movable_with(self.rot, 0, 1) {
+                                self.pos.1 += 1;
+                                rows += 1;
+                                self.last_kick = None;
+                            }
+                            place_tetromino_then_update(self, changes, rows);
+                            return;
+                        }
+                        _ => {}
+                    }
+                }
+                if changes.moved {
+                    self.reset_lock_delay();
+                }
+
+                // soft drop speeds gravity up rather than moving the piece on its own
+                let soft_drop = input.held.contains(Action::SoftDrop)
+                    && self.settings.handling.soft_drop_factor > 0;
+                let factor = match self.settings.handling.soft_drop_factor {
+                    factor if soft_drop && factor > 0 => factor,
+                    _ => 1,
+                };
+                if !self._movable_with(self.rot, 0, 1) {
+                    // the piece rests on the stack, and locks once the lock delay runs out
+                    self.fall = 0;
+                    if self.lock_tick >= self.settings.lock_delay.frames {
+                        place_tetromino_then_update(self, changes, 0);
+                    } else {
+                        self.lock_tick += 1;
+                    }
+                } else {
+                    match self.level.leveling().curve {
+                        LevelCurve::Legacy => {
+                            if self.tick >= self.level.tick_rate / factor {
+                                self.tick = 0;
+                                drop_one_row(self, soft_drop, changes);
+                            } else {
+                                self.tick += 1;
+                            }
+                        }
+                        LevelCurve::Guideline => {
+                            let gravity = self.level.gravity().saturating_mul(factor);
+                            self.fall = self.fall.saturating_add(gravity);
+                            while self.fall >= Level::CELL && self._movable_with(self.rot, 0, 1) {
+                                self.fall -= Level::CELL;
+                                drop_one_row(self, soft_drop, changes);
+                            }
+                        }
+                    }
+                }
+                const fn drop_one_row(game: &mut Game, soft_drop: bool, changes: &mut Changes) {
+                    game.pos.1 += 1;
+                    game.last_kick = None;
+                    changes.moved = true;
+                    if soft_drop {
+                        game.soft_drop_rows += 1;
+                    }
+                    if game.pos.1 > game.lowest_row {
+                        game.lowest_row = game.pos.1;
+                        game.lock_tick = 0;
+                        game.lock_resets = 0;
+                    }
+                }
+                const fn reset_piece(game: &mut Game) {
+                    game.tetromino = game.next_tetromino;
+                    game.next_tetromino = game.randomizer.deal(&mut game.rng);
+                    game.rot = Rotation::DEG0;
+                }
+                // forgets how the previous falling piece moved
+                const fn start_falling(game: &mut Game) {
+                    game.fall = 0;
+                    game.last_kick = None;
+                    game.lock_tick = 0;
+                    game.lock_resets = 0;
+                    game.lowest_row = game.pos.1;
+                }
+                const fn place_tetromino_then_update(
+                    game: &mut Game,
+                    changes: &mut Changes,
+                    hard_drop_rows: u32,
+                ) {
+                    let t_spin = game.t_spin();
+                    let neighbors = game.tetromino.neighbors(game.rot);
+                    let (x, y) = game.pos;
+                    let mut i = 0;
+                    while i < neighbors.len() {
+                        let (dx, dy) = neighbors[i];
+                        // the piece was checked to fit, so its minos lie inside the grid
+                        *game.grid.at_mut((x + dx) as u8, (y + dy) as u8) = Some(game.tetromino);
+                        i += 1;
+                    }
+                    let lines = game.grid.squash_filled_rows().count();
+                    let perfect_clear = lines > 0 && game.grid.is_empty();
+                    game.score += game.scoring.award(&LockEvent {
+                        lines,
+                        level: game.level.number(),
+                        soft_drop: game.soft_drop_rows,
+                        hard_drop: hard_drop_rows,
+                        t_spin,
+                        perfect_clear,
+                    });
+                    game.soft_drop_rows = 0;
+                    game.lines += lines as u32;
+                    game.level.add_lines(lines as u32);
+                    if lines > 0 {
+                        game.line_clears[lines as usize - 1] += 1;
+                    }
+                    if perfect_clear {
+                        game.perfect_clears += 1;
+                    }
+                    game.pieces += 1;
+                    changes.locked = true;
+                    changes.lines_cleared = lines;
+                    changes.t_spin = t_spin;
+                    changes.perfect_clear = perfect_clear;
+                    if matches!(game.settings.mode, Mode::Sprint)
+                        && game.lines >= Mode::SPRINT_LINES
+                    {
+                        game.state = State::Over;
+                        return;
+                    }
+                    game.pos = Game::SPAWN_POS;
+                    if !game._movable_with(game.rot, 0, 0) {
+                        game.state = State::Over;
+                        return;
+                    }
+                    reset_piece(game);
+                    game.swapped = false;
+                    game.level.update();
+                    game.tick = 0;
+                    start_falling(game);
+                }
+            }
+            State::Pause => {
+                if input.pressed.contains(Action::Confirm) {
+                    self.state = State::Play;
+                } else if input.pressed.contains(Action::Quit) {
+                    self.state = State::WindowClose;
+                }
+            }
+            State::Over => {
+                if input.pressed.contains(Action::Confirm) {
+                    // without a fixed seed, the next game draws its seed from this one, which
+                    // keeps whole sessions reproducible
+                    let seed = match self.settings.seed {
+                        Some(seed) => seed,
+                        None => self.rng.next(),
+                    };
+                    *self = Game::with_seed(self.settings, seed);
+                    self.state = State::Play;
+                } else if input.pressed.contains(Action::Quit) {
+                    self.state = State::WindowClose;
+                }
+            }
+            State::WindowClose => {
+                panic!("`update` method should not be called when the state is in WindowClose");
+            }
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{Actions, LockDelay};
+
+    /// A game with the default settings, past its start screen.
+    pub(crate) fn playing() -> Game {
+        let mut game = Game::with_seed(Settings::DEFAULT, 1);
+        game.update(InputFrame::press(Action::Confirm));
+        game
+    }
+
+    /// A game whose falling piece is `tetromino`, turned by `rot` at `pos`, above a grid with
+    /// `filled` cells.
+    pub(crate) fn with_piece(
+        tetromino: Tetromino,
+        pos: (i8, i8),
+        rot: Rotation,
+        filled: &[(u8, u8)],
+    ) -> Game {
+        let mut game = playing();
+        game.tetromino = tetromino;
+        game.pos = pos;
+        game.rot = rot;
+        for &(x, y) in filled {
+            *game.grid.at_mut(x, y) = Some(Tetromino::O);
+        }
+        game
+    }
+
+    fn filled_cells(game: &Game) -> Vec<(u8, u8)> {
+        let mut cells = Vec::new();
+        for y in 0..Grid::HEIGHT {
+            for x in 0..Grid::WIDTH {
+                if game.grid.at(x, y).is_some() {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn confirm_spawns_the_first_piece() {
+        let mut game = Game::with_seed(Settings::DEFAULT, 1);
+        let (piece, next) = (game.piece(), game.next_piece());
+        assert_eq!(game.update(InputFrame::IDLE).state, None);
+
+        let changes = game.update(InputFrame::press(Action::Confirm));
+        assert_eq!(changes.state, Some(State::Play));
+        assert_eq!(game.piece(), piece);
+        assert_eq!(game.next_piece(), next);
+        assert_eq!(game.position(), Game::SPAWN_POS);
+        assert_eq!(game.rotation(), Rotation::DEG0);
+        assert!(game.grid().is_empty());
+    }
+
+    #[test]
+    fn each_press_shifts_one_column() {
+        let mut game = playing();
+        let (x, y) = Game::SPAWN_POS;
+
+        assert!(game.update(InputFrame::press(Action::Left)).moved);
+        assert_eq!(game.position(), (x - 1, y));
+        game.update(InputFrame::IDLE);
+        game.update(InputFrame::press(Action::Right));
+        game.update(InputFrame::IDLE);
+        game.update(InputFrame::press(Action::Right));
+        assert_eq!(game.position(), (x + 1, y));
+    }
+
+    #[test]
+    fn held_shift_slides_to_the_wall() {
+        let mut game = playing();
+        let mut input = InputFrame::press(Action::Left);
+        for _ in 0..60 {
+            game.update(input);
+            input.pressed = Actions::NONE;
+        }
+        let neighbors = game.piece().neighbors(game.rotation());
+        let left = neighbors
+            .iter()
+            .map(|&(dx, _)| game.position().0 + dx)
+            .min();
+        assert_eq!(left, Some(0));
+    }
+
+    #[test]
+    fn hard_drop_locks_the_piece_on_the_floor() {
+        let mut game = playing();
+        let next = game.next_piece();
+
+        let changes = game.update(InputFrame::press(Action::HardDrop));
+        assert!(changes.locked);
+        assert_eq!(changes.lines_cleared, 0);
+        assert_eq!(game.pieces(), 1);
+        assert_eq!(game.piece(), next);
+        assert_eq!(game.position(), Game::SPAWN_POS);
+        let cells = filled_cells(&game);
+        assert_eq!(cells.len(), 4);
+        assert!(cells.iter().any(|&(_, y)| y == Grid::HEIGHT - 1));
+    }
+
+    #[test]
+    fn hold_swaps_once_per_piece() {
+        let mut game = playing();
+        let (first, second) = (game.piece(), game.next_piece());
+
+        let changes = game.update(InputFrame::press(Action::Hold));
+        assert!(changes.held);
+        assert_eq!(game.held_piece(), Some(first));
+        assert_eq!(game.piece(), second);
+        assert!(!game.can_hold());
+
+        assert!(!game.update(InputFrame::press(Action::Hold)).held);
+        assert_eq!(game.piece(), second);
+
+        game.update(InputFrame::press(Action::HardDrop));
+        assert!(game.can_hold());
+        let third = game.piece();
+        game.update(InputFrame::press(Action::Hold));
+        assert_eq!(game.piece(), first);
+        assert_eq!(game.held_piece(), Some(third));
+    }
+
+    #[test]
+    fn topping_out_ends_the_game() {
+        let mut game = playing();
+        // a stack up to the spawn rows, with a well so that no row clears
+        for y in 2..Grid::HEIGHT {
+            for x in 1..Grid::WIDTH {
+                *game.grid.at_mut(x, y) = Some(Tetromino::O);
+            }
+        }
+
+        let changes = game.update(InputFrame::press(Action::HardDrop));
+        assert!(changes.locked);
+        assert_eq!(changes.state, Some(State::Over));
+        assert_eq!(game.update(InputFrame::press(Action::HardDrop)).state, None);
+
+        let changes = game.update(InputFrame::press(Action::Confirm));
+        assert_eq!(changes.state, Some(State::Play));
+        assert!(game.grid().is_empty());
+        assert_eq!(game.pieces(), 0);
+    }
+
+    #[test]
+    fn t_spin_double() {
+        // a slot two rows deep under an overhang on its left
+        let mut filled = vec![(3, 19)];
+        filled.extend((0..10).filter(|x| !(3..6).contains(x)).map(|x| (x, 20)));
+        filled.extend((0..10).filter(|&x| x != 4).map(|x| (x, 21)));
+        let mut game = with_piece(Tetromino::T, (4, 20), Rotation::DEG90, &filled);
+
+        game.update(InputFrame::press(Action::RotateCw));
+        assert_eq!(
+            (game.position(), game.rotation()),
+            ((4, 20), Rotation::DEG180)
+        );
+        let changes = game.update(InputFrame::press(Action::HardDrop));
+        assert_eq!(changes.t_spin, Some(TSpin::Full));
+        assert_eq!(changes.lines_cleared, 2);
+    }
+
+    #[test]
+    fn mini_t_spin_against_the_wall() {
+        // the wall fills both corners on the left, and one block the corner below the point
+        let mut game = with_piece(Tetromino::T, (1, 20), Rotation::DEG0, &[(1, 21)]);
+
+        game.update(InputFrame::press(Action::RotateCw));
+        assert_eq!(
+            (game.position(), game.rotation()),
+            ((0, 20), Rotation::DEG90)
+        );
+        let changes = game.update(InputFrame::press(Action::HardDrop));
+        assert_eq!(changes.t_spin, Some(TSpin::Mini));
+    }
+
+    #[test]
+    fn last_kick_upgrades_a_mini_t_spin() {
+        // only one corner the T points to is filled, but it needs the last kick to get in
+        let filled = [(0, 19), (2, 19), (0, 21), (1, 17)];
+        let mut game = with_piece(Tetromino::T, (2, 18), Rotation::DEG0, &filled);
+
+        game.update(InputFrame::press(Action::RotateCw));
+        assert_eq!(
+            (game.position(), game.rotation()),
+            ((1, 20), Rotation::DEG90)
+        );
+        assert_eq!(game.last_kick, Some(4));
+        let changes = game.update(InputFrame::press(Action::HardDrop));
+        assert_eq!(changes.t_spin, Some(TSpin::Full));
+    }
+
+    #[test]
+    fn moving_after_turning_is_no_t_spin() {
+        // the spot of the mini T-spin against the wall, reached by a drop instead of a turn
+        let mut game = with_piece(Tetromino::T, (1, 10), Rotation::DEG0, &[(1, 21)]);
+
+        game.update(InputFrame::press(Action::RotateCw));
+        game.update(InputFrame::press(Action::Left));
+        assert_eq!(
+            (game.position(), game.rotation()),
+            ((0, 10), Rotation::DEG90)
+        );
+        let changes = game.update(InputFrame::press(Action::HardDrop));
+        assert!(changes.locked);
+        assert_eq!(changes.t_spin, None);
+        assert!(game.grid.at(0, 21).is_some() && game.grid.at(1, 20).is_some());
+    }
+
+    fn both(first: Action, second: Action) -> InputFrame {
+        let actions = Actions::NONE.with(first).with(second);
+        InputFrame {
+            pressed: actions,
+            held: actions,
+        }
+    }
+
+    #[test]
+    fn turns_and_shifts_in_one_frame() {
+        let mut game = with_piece(Tetromino::T, (4, 10), Rotation::DEG0, &[]);
+        let changes = game.update(both(Action::RotateCw, Action::Left));
+        assert!(changes.moved);
+        assert_eq!(game.rotation(), Rotation::DEG90);
+        assert_eq!(game.position(), (3, 10));
+    }
+
+    #[test]
+    fn holds_before_hard_dropping_in_one_frame() {
+        let mut game = playing();
+        let (piece, next) = (game.piece(), game.next_piece());
+        let changes = game.update(both(Action::HardDrop, Action::Hold));
+        assert!(changes.held && changes.locked);
+        assert_eq!(game.held_piece(), Some(piece));
+        // the piece taken from the queue is the one dropped, and the next one may be held again
+        let cells = filled_cells(&game);
+        assert_eq!(cells.len(), 4);
+        assert!(cells
+            .iter()
+            .all(|&(x, y)| game.grid.at(x, y) == &Some(next)));
+        assert!(game.can_hold());
+    }
+
+    #[test]
+    fn emptying_the_grid_is_a_perfect_clear() {
+        let row: Vec<_> = (4..Grid::WIDTH).map(|x| (x, 21)).collect();
+        let mut game = with_piece(Tetromino::I, (1, 10), Rotation::DEG0, &row);
+        let changes = game.update(InputFrame::press(Action::HardDrop));
+        assert_eq!(changes.lines_cleared, 1);
+        assert!(changes.perfect_clear);
+        assert_eq!(game.perfect_clears(), 1);
+        assert!(filled_cells(&game).is_empty());
+
+        // clearing a row over leftover cells is not
+        let mut cells = row.clone();
+        cells.push((9, 20));
+        let mut game = with_piece(Tetromino::I, (1, 10), Rotation::DEG0, &cells);
+        let changes = game.update(InputFrame::press(Action::HardDrop));
+        assert_eq!(changes.lines_cleared, 1);
+        assert!(!changes.perfect_clear);
+        assert_eq!(game.perfect_clears(), 0);
+    }
+
+    /// An O piece resting at `pos` above `filled` cells, which waits for `lock_delay` to lock.
+    fn resting(lock_delay: LockDelay, pos: (i8, i8), filled: &[(u8, u8)]) -> Game {
+        let mut game = with_piece(Tetromino::O, pos, Rotation::DEG0, filled);
+        game.settings.lock_delay = lock_delay;
+        game.lowest_row = pos.1;
+        game
+    }
+
+    /// Plays until the piece locks, tapping left and right in turn every `tap_every` frames, and
+    /// returns the frame it locked on, if it did within ten seconds.
+    fn frames_to_lock(game: &mut Game, tap_every: u32) -> Option<u32> {
+        (1..=600).find(|frame| {
+            let input = match frame % tap_every {
+                0 if frame / tap_every % 2 == 1 => InputFrame::press(Action::Left),
+                0 => InputFrame::press(Action::Right),
+                _ => InputFrame::IDLE,
+            };
+            game.update(input).locked
+        })
+    }
+
+    fn lock_delay(reset: LockReset) -> LockDelay {
+        LockDelay {
+            reset,
+            ..LockDelay::DEFAULT
+        }
+    }
+
+    #[test]
+    fn infinity_never_locks_a_moving_piece() {
+        let mut game = resting(lock_delay(LockReset::Infinity), (4, 21), &[]);
+        assert_eq!(frames_to_lock(&mut game, 5), None);
+        // the last tap was on the last frame, and restarted the wait
+        assert_eq!(frames_to_lock(&mut game, u32::MAX), Some(30));
+    }
+
+    #[test]
+    fn move_reset_stops_after_the_last_reset() {
+        let mut game = resting(lock_delay(LockReset::MoveReset), (4, 21), &[]);
+        assert_eq!(frames_to_lock(&mut game, u32::MAX), Some(31));
+
+        // the 15th tap, on frame 75, restarts the wait one last time
+        let mut game = resting(lock_delay(LockReset::MoveReset), (4, 21), &[]);
+        assert_eq!(frames_to_lock(&mut game, 5), Some(105));
+    }
+
+    #[test]
+    fn step_reset_ignores_moves() {
+        let mut game = resting(lock_delay(LockReset::StepReset), (4, 21), &[]);
+        assert_eq!(frames_to_lock(&mut game, 5), Some(31));
+    }
+
+    #[test]
+    fn falling_below_the_lowest_row_restarts_the_wait() {
+        // a ledge under the left half of the board, with the piece on its edge
+        let ledge: Vec<_> = (0..6).map(|x| (x, 21)).collect();
+        let mut game = resting(lock_delay(LockReset::MoveReset), (4, 20), &ledge);
+        for _ in 0..8 {
+            game.update(InputFrame::IDLE);
+            game.update(InputFrame::press(Action::Right));
+            game.update(InputFrame::IDLE);
+            game.update(InputFrame::press(Action::Left));
+        }
+        assert_eq!(game.lock_resets, 15);
+
+        game.update(InputFrame::press(Action::Right));
+        game.update(InputFrame::IDLE);
+        game.update(InputFrame::press(Action::Right));
+        assert_eq!(game.position(), (6, 20));
+        let fell = (0..60).position(|_| {
+            game.update(InputFrame::IDLE);
+            game.position().1 == 21
+        });
+        assert!(fell.is_some());
+        assert_eq!((game.lock_tick, game.lock_resets), (0, 0));
+        assert_eq!(frames_to_lock(&mut game, u32::MAX), Some(31));
+    }
+
+    #[test]
+    fn no_lock_delay_locks_on_landing() {
+        let no_delay = LockDelay {
+            frames: 0,
+            ..LockDelay::DEFAULT
+        };
+        let mut game = resting(no_delay, (4, 21), &[]);
+        assert_eq!(frames_to_lock(&mut game, 1), Some(1));
+
+        // a falling piece locks on the frame after gravity brings it down
+        let mut game = resting(no_delay, (4, 20), &[]);
+        game.fall = Level::CELL - 1;
+        let changes = game.update(InputFrame::IDLE);
+        assert!(changes.moved && !changes.locked);
+        assert!(game.update(InputFrame::IDLE).locked);
+    }
+}
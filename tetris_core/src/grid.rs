@@ -0,0 +1,135 @@
+use crate::Tetromino;
+
+/// The playfield, stored row by row from the top-left corner.
+#[derive(Clone)]
+pub struct Grid {
+    cells: [Option<Tetromino>; Grid::WIDTH as usize * Grid::HEIGHT as usize],
+}
+
+impl Grid {
+    /// Number of columns in the playfield.
+    pub const WIDTH: u8 = 10;
+    /// Number of rows in the playfield.
+    pub const HEIGHT: u8 = 22;
+
+    /// Creates an empty playfield.
+    pub const fn new() -> Grid {
+        Grid {
+            cells: [None; Grid::WIDTH as usize * Grid::HEIGHT as usize],
+        }
+    }
+
+    /// Remove filled rows and move other rows downward.
+    /// Returns the rows that were removed.
+    pub const fn squash_filled_rows(&mut self) -> ClearedRows {
+        let mut cleared = ClearedRows {
+            rows: [0; Grid::HEIGHT as usize],
+            len: 0,
+        };
+        let mut y = Grid::HEIGHT;
+        while y > 0 {
+            y -= 1;
+            if self.row_filled(y) {
+                cleared.rows[cleared.len as usize] = y;
+                cleared.len += 1;
+            } else if cleared.len > 0 {
+                self.copy_row(y, y + cleared.len);
+            }
+        }
+        let mut y = 0;
+        while y < cleared.len {
+            self.clear_row(y);
+            y += 1;
+        }
+        cleared
+    }
+
+    /// Whether no cell of the playfield is filled, as after a perfect clear.
+    pub const fn is_empty(&self) -> bool {
+        let mut i = 0;
+        while i < self.cells.len() {
+            if self.cells[i].is_some() {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    const fn row_filled(&self, y: u8) -> bool {
+        let mut x = 0;
+        while x < Grid::WIDTH {
+            if self.at(x, y).is_none() {
+                return false;
+            }
+            x += 1;
+        }
+        true
+    }
+
+    const fn copy_row(&mut self, y_src: u8, y_dst: u8) {
+        let mut x = 0;
+        while x < Grid::WIDTH {
+            *self.at_mut(x, y_dst) = *self.at(x, y_src);
+            x += 1;
+        }
+    }
+
+    const fn clear_row(&mut self, y: u8) {
+        let mut x = 0;
+        while x < Grid::WIDTH {
+            *self.at_mut(x, y) = None;
+            x += 1;
+        }
+    }
+
+    /// Returns the cell at column `x` and row `y`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates lie outside of the playfield.
+    pub const fn at(&self, x: u8, y: u8) -> &Option<Tetromino> {
+        assert!(x < Self::WIDTH);
+        assert!(y < Self::HEIGHT);
+        let idx = (y * Grid::WIDTH + x) as usize;
+        &self.cells[idx]
+    }
+
+    /// Returns the cell at column `x` and row `y` mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates lie outside of the playfield.
+    pub const fn at_mut(&mut self, x: u8, y: u8) -> &mut Option<Tetromino> {
+        assert!(x < Self::WIDTH);
+        assert!(y < Self::HEIGHT);
+        let idx = (y * Grid::WIDTH + x) as usize;
+        &mut self.cells[idx]
+    }
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Grid::new()
+    }
+}
+
+/// The rows removed by [`Grid::squash_filled_rows`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClearedRows {
+    rows: [u8; Grid::HEIGHT as usize],
+    len: u8,
+}
+
+impl ClearedRows {
+    /// Number of rows removed.
+    pub const fn count(&self) -> u8 {
+        self.len
+    }
+
+    /// The rows removed, from the bottom up, numbered from the top of the grid as it was before
+    /// they were removed.
+    pub const fn rows(&self) -> &[u8] {
+        self.rows.split_at(self.len as usize).0
+    }
+}
@@ -0,0 +1,416 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+#[cfg(unix)]
+use std::path::Path;
+
+use crate::json::{self, Value};
+use crate::{Action, Actions, Game, InputFrame, Rotation, Settings, State, Summary};
+
+/// A headless game driven by a line-delimited JSON protocol, so that external agents can be
+/// trained against the very rules that gate the build.
+///
+/// Every request is a JSON object on its own line, answered by exactly one line:
+///
+/// - `{"cmd": "reset", "seed": 42}` starts a new game and answers with its first observation.
+///   The `seed` is optional, as are `"mode"`, `"randomizer"`, `"rotation"` and `"scoring"`,
+///   which take the names `run!` accepts.
+/// - `{"cmd": "step", "actions": ["left", "rotate_cw"]}` plays one frame with the actions
+///   pressed, or none without `actions`. A single `"action"` may be given instead, and
+///   `"frames": n` keeps the actions held for `n` frames, from 1 to [`Gym::MAX_FRAMES`], or
+///   until the game ends. Keys are released between requests, so that repeated steps tap
+///   them.
+/// - `{"cmd": "observe"}` answers with the current observation without playing.
+/// - `{"cmd": "close"}` ends the session.
+///
+/// Observations hold the `state` of the game (`start`, `play`, `pause`, `over`, or `closed` once
+/// the player quit), the `score`, the `reward` earned by the request, whether the game is
+/// `done`, the `lines`, `perfect_clears`, `level`, `pieces` and `frames` so far, the falling
+/// `piece` with its `position` and `rotation` in clockwise quarter turns, the `hold` piece,
+/// whether `can_hold`, the `queue` of next pieces, and the `board` as rows of `.` and piece
+/// letters from the top. A malformed request is answered with `{"error": "<message>"}`.
+///
+/// The actions are `left`, `right`, `soft_drop`, `hard_drop`, `rotate_cw`, `rotate_ccw`,
+/// `hold`, `pause`, `confirm` and `quit`.
+pub struct Gym {
+    settings: Settings,
+    game: Option<Game>,
+}
+
+impl Gym {
+    /// Most frames a single `step` request may play, a minute of play.
+    pub const MAX_FRAMES: u32 = 60 * 60;
+
+    /// Creates a gym whose games are played with `settings` unless a reset overrides them.
+    pub fn new(settings: Settings) -> Gym {
+        Gym {
+            settings,
+            game: None,
+        }
+    }
+
+    /// Answers the requests read from `input` on `output` until the input ends or a `close`
+    /// request comes, for instance over stdin and stdout.
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (response, close) = self.respond(&line);
+            writeln!(output, "{response}")?;
+            output.flush()?;
+            if close {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Listens on the Unix socket at `path` and serves each connection in turn, with a gym of
+    /// its own.
+    ///
+    /// A socket left at `path` by a previous run is replaced. A connection that fails, for
+    /// instance because its client hung up mid-response, is reported on stderr and dropped,
+    /// and the next one is served.
+    #[cfg(unix)]
+    pub fn serve_unix(settings: Settings, path: impl AsRef<Path>) -> io::Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+        use std::os::unix::net::UnixListener;
+
+        let path = path.as_ref();
+        if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        for stream in listener.incoming() {
+            let result = stream
+                .and_then(|stream| Gym::new(settings).serve(io::BufReader::new(&stream), &stream));
+            if let Err(err) = result {
+                eprintln!("warning: gym connection failed: {err}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers one request, given as a line of JSON, with one line of JSON.
+    pub fn handle(&mut self, request: &str) -> String {
+        self.respond(request).0
+    }
+
+    /// Answers `request`, telling whether it closes the session.
+    fn respond(&mut self, request: &str) -> (String, bool) {
+        match self.execute(request) {
+            Ok(Some(response)) => (response, false),
+            Ok(None) => ("{\"closed\":true}".to_owned(), true),
+            Err(message) => {
+                let mut response = "{\"error\":".to_owned();
+                json::write_string(&mut response, &message);
+                response.push('}');
+                (response, false)
+            }
+        }
+    }
+
+    fn execute(&mut self, request: &str) -> Result<Option<String>, String> {
+        let request = json::parse(request)?;
+        let command = match request.get("cmd") {
+            Some(Value::String(command)) => command.as_str(),
+            _ => return Err("expected a `cmd` member naming the command".to_owned()),
+        };
+        let response = match command {
+            "reset" => {
+                let mut settings = self.settings;
+                settings.seed = match request.get("seed") {
+                    None | Some(Value::Null) => None,
+                    Some(Value::Number(seed)) => Some(
+                        seed.parse()
+                            .map_err(|_| "the seed should be an unsigned 64-bit integer")?,
+                    ),
+                    Some(_) => return Err("the seed should be a number".to_owned()),
+                };
+                if let Some(mode) = name(&request, "mode")? {
+                    settings.mode = mode;
+                }
+                if let Some(randomizer) = name(&request, "randomizer")? {
+                    settings.randomizer = randomizer;
+                }
+                if let Some(rotation) = name(&request, "rotation")? {
+                    settings.rotation_system = rotation;
+                }
+                if let Some(scoring) = name(&request, "scoring")? {
+                    settings.scoring = scoring;
+                }
+                let mut game = Game::with_settings(settings);
+                game.update(InputFrame::press(Action::Confirm));
+                let response = observation(&game, 0);
+                self.game = Some(game);
+                response
+            }
+            "step" => {
+                let mut actions = Vec::new();
+                match request.get("action") {
+                    None | Some(Value::Null) => {}
+                    Some(action) => actions.push(action),
+                }
+                match request.get("actions") {
+                    None | Some(Value::Null) => {}
+                    Some(Value::Array(items)) => actions.extend(items),
+                    Some(_) => return Err("`actions` should be an array".to_owned()),
+                }
+                let mut input = InputFrame::IDLE;
+                for action in actions {
+                    let Value::String(action) = action else {
+                        return Err("actions should be strings".to_owned());
+                    };
+                    let action: Action = action
+                        .parse()
+                        .map_err(|err| format!("unknown action `{action}`, {err}"))?;
+                    input.pressed.insert(action);
+                    input.held.insert(action);
+                }
+                let frames = match request.get("frames") {
+                    None => 1,
+                    Some(Value::Number(frames)) => frames
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|frames| (1..=Gym::MAX_FRAMES).contains(frames))
+                        .ok_or_else(|| {
+                            format!(
+                                "`frames` should be an integer from 1 to {}",
+                                Gym::MAX_FRAMES
+                            )
+                        })?,
+                    Some(_) => return Err("`frames` should be a number".to_owned()),
+                };
+
+                let game = self
+                    .game
+                    .as_mut()
+                    .ok_or("no game is running, send a `reset` first")?;
+                let score = game.score();
+                game.release_keys();
+                for frame in 0..frames {
+                    // the first frame may still restart a finished game
+                    let done = match game.state {
+                        State::Over => frame > 0,
+                        State::WindowClose => true,
+                        _ => false,
+                    };
+                    if done {
+                        break;
+                    }
+                    let input = if frame == 0 {
+                        input
+                    } else {
+                        InputFrame {
+                            pressed: Actions::NONE,
+                            held: input.held,
+                        }
+                    };
+                    game.update(input);
+                }
+                observation(game, game.score().saturating_sub(score))
+            }
+            "observe" => {
+                let game = self
+                    .game
+                    .as_ref()
+                    .ok_or("no game is running, send a `reset` first")?;
+                observation(game, 0)
+            }
+            "close" => return Ok(None),
+            _ => {
+                return Err(format!(
+                    "unknown command `{command}`, expected one of `reset`, `step`, `observe`, \
+                     `close`"
+                ))
+            }
+        };
+        Ok(Some(response))
+    }
+}
+
+/// Reads the optional member `key` of `request` as the name of a setting.
+fn name<T: std::str::FromStr<Err = crate::ParseSettingError>>(
+    request: &Value,
+    key: &str,
+) -> Result<Option<T>, String> {
+    match request.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => value
+            .parse()
+            .map(Some)
+            .map_err(|err| format!("unknown {key} `{value}`, {err}")),
+        Some(_) => Err(format!("the {key} should be a string")),
+    }
+}
+
+fn observation(game: &Game, reward: u32) -> String {
+    let state = match game.state {
+        State::Start => "start",
+        State::Play => "play",
+        State::Pause => "pause",
+        State::Over => "over",
+        State::WindowClose => "closed",
+    };
+    let done = matches!(game.state, State::Over | State::WindowClose);
+    let rotation = match game.rotation() {
+        Rotation::DEG0 => 0,
+        Rotation::DEG90 => 1,
+        Rotation::DEG180 => 2,
+        Rotation::DEG270 => 3,
+    };
+    let (x, y) = game.position();
+    let hold = game.held_piece().map_or("null".to_owned(), |piece| {
+        format!("\"{}\"", piece.to_char())
+    });
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "{{\"state\":\"{state}\",\"score\":{},\"reward\":{reward},\"done\":{done},\
+         \"lines\":{},\"perfect_clears\":{},\"level\":{},\"pieces\":{},\"frames\":{},\
+         \"piece\":\"{}\",\"position\":[{x},{y}],\"rotation\":{rotation},\
+         \"hold\":{hold},\"can_hold\":{},\"queue\":[\"{}\"],\"board\":[",
+        game.score(),
+        game.lines(),
+        game.perfect_clears(),
+        game.level().number(),
+        game.pieces(),
+        game.frames(),
+        game.piece().to_char(),
+        game.can_hold(),
+        game.next_piece().to_char(),
+    );
+    for (i, row) in Summary::of(game).board_rows().iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        json::write_string(&mut out, row);
+    }
+    out.push_str("]}");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends `request` to `gym` and parses its answer.
+    fn send(gym: &mut Gym, request: &str) -> Value {
+        json::parse(&gym.handle(request)).unwrap()
+    }
+
+    fn number(value: &Value, key: &str) -> u64 {
+        match value.get(key) {
+            Some(Value::Number(number)) => number.parse().unwrap(),
+            other => panic!("`{key}` should be a number, got {other:?}"),
+        }
+    }
+
+    fn error(value: &Value) -> &str {
+        match value.get("error") {
+            Some(Value::String(message)) => message,
+            other => panic!("expected an error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reset_starts_a_seeded_game() {
+        let mut gym = Gym::new(Settings::DEFAULT);
+        let first = send(&mut gym, r#"{"cmd": "reset", "seed": 42}"#);
+        assert_eq!(first.get("state"), Some(&Value::String("play".to_owned())));
+        assert_eq!(first.get("done"), Some(&Value::Bool(false)));
+        assert_eq!(number(&first, "score"), 0);
+        assert_eq!(send(&mut gym, r#"{"cmd": "reset", "seed": 42}"#), first);
+    }
+
+    #[test]
+    fn step_plays_frames_and_observe_does_not() {
+        let mut gym = Gym::new(Settings::DEFAULT);
+        send(&mut gym, r#"{"cmd": "reset", "seed": 1}"#);
+        let step = send(&mut gym, r#"{"cmd": "step", "frames": 10}"#);
+        assert_eq!(number(&step, "frames"), 10);
+        assert_eq!(send(&mut gym, r#"{"cmd": "observe"}"#), step);
+
+        let step = send(&mut gym, r#"{"cmd": "step", "action": "hard_drop"}"#);
+        assert_eq!(number(&step, "pieces"), 1);
+    }
+
+    #[test]
+    fn repeated_steps_tap_the_keys() {
+        let mut gym = Gym::new(Settings::DEFAULT);
+        let start = send(&mut gym, r#"{"cmd": "reset", "seed": 1}"#);
+        let x = |observation: &Value| match observation.get("position") {
+            Some(Value::Array(position)) => match &position[0] {
+                Value::Number(x) => x.parse::<i64>().unwrap(),
+                other => panic!("expected a column, got {other:?}"),
+            },
+            other => panic!("expected a position, got {other:?}"),
+        };
+        let column = x(&start);
+        for taps in 1..=3 {
+            let step = send(&mut gym, r#"{"cmd": "step", "action": "left"}"#);
+            assert_eq!(x(&step), column - taps);
+        }
+    }
+
+    #[test]
+    fn confirm_restarts_a_finished_game() {
+        let mut gym = Gym::new(Settings::DEFAULT);
+        // hard drops earn points with the guideline scoring, which the restart must not take back
+        send(
+            &mut gym,
+            r#"{"cmd": "reset", "seed": 1, "scoring": "guideline"}"#,
+        );
+        let mut over = None;
+        for _ in 0..100 {
+            let step = send(&mut gym, r#"{"cmd": "step", "action": "hard_drop"}"#);
+            if step.get("done") == Some(&Value::Bool(true)) {
+                over = Some(step);
+                break;
+            }
+        }
+        let over = over.expect("stacking pieces in the middle should top out");
+        assert!(number(&over, "score") > 0);
+        let idle = send(&mut gym, r#"{"cmd": "step", "frames": 3600}"#);
+        assert_eq!(number(&idle, "frames"), number(&over, "frames"));
+        assert_eq!(idle.get("done"), Some(&Value::Bool(true)));
+
+        let restarted = send(&mut gym, r#"{"cmd": "step", "action": "confirm"}"#);
+        assert_eq!(
+            restarted.get("state"),
+            Some(&Value::String("play".to_owned()))
+        );
+        assert_eq!(number(&restarted, "reward"), 0);
+    }
+
+    #[test]
+    fn malformed_requests_get_errors() {
+        let mut gym = Gym::new(Settings::DEFAULT);
+        assert!(error(&send(&mut gym, r#"{"cmd": "step"}"#)).contains("reset"));
+        assert!(error(&send(&mut gym, "[1, 2")).contains("column"));
+        assert!(error(&send(&mut gym, r#"{"cmd": "jump"}"#)).contains("unknown command"));
+        assert!(error(&send(&mut gym, r#"{"cmd": "reset", "mode": "zen"}"#)).contains("mode"));
+
+        send(&mut gym, r#"{"cmd": "reset"}"#);
+        assert!(error(&send(&mut gym, r#"{"cmd": "step", "action": "jump"}"#)).contains("jump"));
+        for frames in ["0", "3601", "-1"] {
+            let request = format!(r#"{{"cmd": "step", "frames": {frames}}}"#);
+            assert!(error(&send(&mut gym, &request)).contains("frames"));
+        }
+    }
+
+    #[test]
+    fn close_ends_the_session() {
+        let input = "{\"cmd\": \"reset\"}\n\n{\"cmd\": \"close\"}\n{\"cmd\": \"observe\"}\n";
+        let mut output = Vec::new();
+        Gym::new(Settings::DEFAULT)
+            .serve(input.as_bytes(), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], r#"{"closed":true}"#);
+    }
+}
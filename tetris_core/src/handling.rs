@@ -0,0 +1,186 @@
+use crate::{Action, InputFrame};
+
+/// How held keys repeat, as in modern Tetris games. Every duration is counted in frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handling {
+    /// Delayed auto shift: how long a shift key is held, after its first shift, before the
+    /// piece starts moving on its own.
+    pub das: u32,
+    /// Auto repeat rate: the delay between two automatic shifts once DAS has elapsed. With `0`,
+    /// the piece slides to the wall at once.
+    pub arr: u32,
+    /// Soft drop factor: how many times faster than gravity the piece falls while soft drop is
+    /// held. With `0`, soft drop does nothing.
+    pub soft_drop_factor: u32,
+}
+
+impl Handling {
+    /// The default handling: a DAS of 10 frames, an ARR of 2 frames and a soft drop 20 times
+    /// faster than gravity.
+    pub const DEFAULT: Handling = Handling {
+        das: 10,
+        arr: 2,
+        soft_drop_factor: 20,
+    };
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling::DEFAULT
+    }
+}
+
+/// How long each shift key has been held, which decides when it repeats.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct KeyRepeat {
+    /// Frames the left and right keys have been held, `0` once released.
+    held: [u32; 2],
+}
+
+impl KeyRepeat {
+    pub(crate) const fn new() -> KeyRepeat {
+        KeyRepeat { held: [0; 2] }
+    }
+
+    /// Counts one more frame of `input` and returns the direction to shift the piece in, along
+    /// with the number of columns to shift it by, [`u8::MAX`] meaning up to the wall.
+    ///
+    /// When both keys are held, the one pressed last wins.
+    pub(crate) const fn shift(&mut self, input: InputFrame, handling: Handling) -> (i8, u8) {
+        let mut i = 0;
+        while i < self.held.len() {
+            let action = if i == 0 { Action::Left } else { Action::Right };
+            self.held[i] = if input.held.contains(action) {
+                self.held[i].saturating_add(1)
+            } else {
+                0
+            };
+            i += 1;
+        }
+
+        let [left, right] = self.held;
+        let (direction, frames) = if left > 0 && (right == 0 || left < right) {
+            (-1, left)
+        } else if right > 0 && (left == 0 || right < left) {
+            (1, right)
+        } else {
+            return (0, 0);
+        };
+        let cells = if frames == 1 {
+            1
+        } else if frames < 1 + handling.das {
+            0
+        } else if handling.arr == 0 {
+            u8::MAX
+        } else if (frames - 1 - handling.das).is_multiple_of(handling.arr) {
+            1
+        } else {
+            0
+        };
+        (direction, cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Actions, Game, ScoringKind, Settings};
+
+    fn hold(action: Action) -> InputFrame {
+        InputFrame {
+            pressed: Actions::NONE,
+            held: Actions::NONE.with(action),
+        }
+    }
+
+    /// The columns shifted on each of the first `frames` frames `Left` is held.
+    fn shifts(handling: Handling, frames: usize) -> Vec<u8> {
+        let mut repeat = KeyRepeat::new();
+        (0..frames)
+            .map(|_| {
+                let (direction, cells) = repeat.shift(hold(Action::Left), handling);
+                assert_eq!(direction, -1);
+                cells
+            })
+            .collect()
+    }
+
+    #[test]
+    fn das_delays_the_repeat() {
+        let shifts = shifts(Handling::DEFAULT, 11);
+        assert_eq!(shifts, [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn arr_sets_the_cadence() {
+        let handling = Handling {
+            das: 4,
+            arr: 3,
+            ..Handling::DEFAULT
+        };
+        assert_eq!(shifts(handling, 12), [1, 0, 0, 0, 1, 0, 0, 1, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn zero_arr_slides_to_the_wall() {
+        let handling = Handling {
+            das: 2,
+            arr: 0,
+            ..Handling::DEFAULT
+        };
+        assert_eq!(shifts(handling, 4), [1, 0, u8::MAX, u8::MAX]);
+    }
+
+    #[test]
+    fn releasing_starts_over() {
+        let mut repeat = KeyRepeat::new();
+        for _ in 0..20 {
+            repeat.shift(hold(Action::Left), Handling::DEFAULT);
+        }
+        assert_eq!(repeat.shift(InputFrame::IDLE, Handling::DEFAULT), (0, 0));
+        assert_eq!(repeat.shift(hold(Action::Right), Handling::DEFAULT), (1, 1));
+        assert_eq!(repeat.shift(hold(Action::Right), Handling::DEFAULT), (1, 0));
+    }
+
+    /// A game with a soft drop `factor` times faster than gravity, where soft drop was held for
+    /// `frames` frames after the start screen, and the rows the piece fell meanwhile.
+    fn soft_dropped(factor: u32, frames: u32) -> (Game, i8) {
+        let settings = Settings {
+            scoring: ScoringKind::Guideline,
+            handling: Handling {
+                soft_drop_factor: factor,
+                ..Handling::DEFAULT
+            },
+            ..Settings::DEFAULT
+        };
+        let mut game = Game::with_seed(settings, 1);
+        game.update(InputFrame::press(Action::Confirm));
+        let y = game.position().1;
+        for _ in 0..frames {
+            game.update(hold(Action::SoftDrop));
+        }
+        let rows = game.position().1 - y;
+        (game, rows)
+    }
+
+    #[test]
+    fn soft_drop_factor_multiplies_gravity() {
+        // a row takes 60 frames on level 1, and 3 frames 20 times faster
+        assert_eq!(soft_dropped(20, 30).1, 10);
+        assert_eq!(soft_dropped(1, 60).1, 1);
+        assert_eq!(soft_dropped(0, 30).1, 0);
+    }
+
+    #[test]
+    fn soft_drop_scores_only_when_it_speeds_the_piece_up() {
+        let hard_drop = |(mut game, _): (Game, i8)| {
+            game.update(InputFrame::press(Action::HardDrop));
+            game.score()
+        };
+        let plain = hard_drop(soft_dropped(20, 0));
+        // without a factor, the row the piece fell in 70 frames is gravity's and earns nothing
+        assert_eq!(hard_drop(soft_dropped(0, 70)), plain - 2);
+        // every row soft dropped is a point, instead of two for hard dropping it
+        assert_eq!(hard_drop(soft_dropped(20, 30)), plain - 10);
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ParseSettingError;
+
+/// Something the player can ask the game to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Action {
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    RotateCw,
+    RotateCcw,
+    Hold,
+    Pause,
+    Confirm,
+    Quit,
+}
+
+impl Action {
+    /// Every action, in declaration order.
+    pub const ALL: [Action; 10] = [
+        Action::Left,
+        Action::Right,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::RotateCw,
+        Action::RotateCcw,
+        Action::Hold,
+        Action::Pause,
+        Action::Confirm,
+        Action::Quit,
+    ];
+
+    /// The name of the action in `snake_case`, such as `hard_drop`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::SoftDrop => "soft_drop",
+            Action::HardDrop => "hard_drop",
+            Action::RotateCw => "rotate_cw",
+            Action::RotateCcw => "rotate_ccw",
+            Action::Hold => "hold",
+            Action::Pause => "pause",
+            Action::Confirm => "confirm",
+            Action::Quit => "quit",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name() == s)
+            .ok_or(ParseSettingError {
+                expected: &[
+                    "left",
+                    "right",
+                    "soft_drop",
+                    "hard_drop",
+                    "rotate_cw",
+                    "rotate_ccw",
+                    "hold",
+                    "pause",
+                    "confirm",
+                    "quit",
+                ],
+            })
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A set of [`Action`]s stored as a bit mask.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Actions(u16);
+
+impl Actions {
+    /// The empty set.
+    pub const NONE: Actions = Actions(0);
+
+    /// Rebuilds a set from the value returned by [`Actions::bits`].
+    pub const fn from_bits(bits: u16) -> Actions {
+        Actions(bits & ((1 << Action::ALL.len()) - 1))
+    }
+
+    /// The raw bit mask, one bit per [`Action`] in declaration order.
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Returns the set with `action` added.
+    pub const fn with(self, action: Action) -> Actions {
+        Actions(self.0 | 1 << action as u8)
+    }
+
+    /// Returns the set without `action`.
+    pub const fn without(self, action: Action) -> Actions {
+        Actions(self.0 & !(1 << action as u8))
+    }
+
+    /// Adds `action` to the set.
+    pub fn insert(&mut self, action: Action) {
+        *self = self.with(action);
+    }
+
+    /// Whether `action` is part of the set.
+    pub const fn contains(self, action: Action) -> bool {
+        self.0 & 1 << action as u8 != 0
+    }
+
+    /// Whether the set holds no action at all.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl FromIterator<Action> for Actions {
+    fn from_iter<I: IntoIterator<Item = Action>>(iter: I) -> Self {
+        let mut actions = Actions::NONE;
+        for action in iter {
+            actions.insert(action);
+        }
+        actions
+    }
+}
+
+/// The actions to apply on one frame, taken out one at a time in a fixed order, so that keys
+/// pressed together all take effect.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ActionQueue {
+    actions: Actions,
+    order: &'static [Action],
+    next: usize,
+}
+
+impl ActionQueue {
+    /// Queues `actions` to be applied in `order`. Actions missing from `order` are dropped.
+    pub(crate) const fn new(actions: Actions, order: &'static [Action]) -> ActionQueue {
+        ActionQueue {
+            actions,
+            order,
+            next: 0,
+        }
+    }
+
+    /// Takes out the next action to apply.
+    pub(crate) const fn pop(&mut self) -> Option<Action> {
+        while self.next < self.order.len() {
+            let action = self.order[self.next];
+            self.next += 1;
+            if self.actions.contains(action) {
+                return Some(action);
+            }
+        }
+        None
+    }
+}
+
+/// Everything the player did during one frame.
+///
+/// `pressed` holds the actions whose key went down on this very frame, while `held` holds every
+/// action whose key is down, including the ones that were just pressed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct InputFrame {
+    pub pressed: Actions,
+    pub held: Actions,
+}
+
+impl InputFrame {
+    /// A frame without any input.
+    pub const IDLE: InputFrame = InputFrame {
+        pressed: Actions::NONE,
+        held: Actions::NONE,
+    };
+
+    /// A frame where `action` was just pressed.
+    pub const fn press(action: Action) -> InputFrame {
+        InputFrame {
+            pressed: Actions::NONE.with(action),
+            held: Actions::NONE.with(action),
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ParseSettingError;
+
+/// Controls how fast pieces fall as the game progresses.
+pub struct Level {
+    /// Number of frames between two gravity steps with [`LevelCurve::Legacy`].
+    pub tick_rate: u32,
+    /// Number of pieces spawned so far, including the current one.
+    pub piece_count: u32,
+    /// Number of rows cleared so far.
+    pub lines: u32,
+    leveling: Leveling,
+}
+
+impl Level {
+    /// One cell, in the unit of [`Level::gravity`].
+    pub const CELL: u32 = 1 << 16;
+
+    /// The last piece of each level and the tick rate of that level, with
+    /// [`LevelCurve::Legacy`].
+    const TIERS: [(u32, u32); 9] = [
+        (25, 30),
+        (50, 25),
+        (100, 20),
+        (200, 15),
+        (300, 12),
+        (500, 10),
+        (700, 8),
+        (900, 6),
+        (u32::MAX, 5),
+    ];
+
+    /// The gravity of each level with [`LevelCurve::Guideline`], from the formula of the
+    /// guideline: a piece falls one row every `(0.8 - (level - 1) * 0.007) ^ (level - 1)`
+    /// seconds, up to 20 rows per frame. Gravities are rounded up, so that a row never takes
+    /// a frame longer than the formula says.
+    const GRAVITY: [u32; 20] = {
+        let mut gravity = [0; 20];
+        let mut level = 0;
+        while level < gravity.len() {
+            let base = 0.8 - level as f64 * 0.007;
+            let mut seconds = 1.0;
+            let mut i = 0;
+            while i < level {
+                seconds *= base;
+                i += 1;
+            }
+            let cells = 1.0 / (seconds * 60.0);
+            gravity[level] = if cells >= 20.0 {
+                20 * Level::CELL
+            } else {
+                let exact = cells * Level::CELL as f64;
+                let truncated = exact as u32;
+                if (truncated as f64) < exact {
+                    truncated + 1
+                } else {
+                    truncated
+                }
+            };
+            level += 1;
+        }
+        gravity
+    };
+
+    /// The level at the start of a game with the default [`Leveling`].
+    pub const fn new() -> Level {
+        Level::with_leveling(Leveling::DEFAULT)
+    }
+
+    /// The level at the start of a game that levels up according to `leveling`.
+    pub const fn with_leveling(leveling: Leveling) -> Level {
+        Level {
+            tick_rate: 30,
+            piece_count: 1,
+            lines: 0,
+            leveling,
+        }
+    }
+
+    /// The level number, starting at 1.
+    pub const fn number(&self) -> u32 {
+        match self.leveling.curve {
+            LevelCurve::Guideline => match self.leveling.lines_per_level {
+                0 => 1,
+                lines_per_level => self.lines / lines_per_level + 1,
+            },
+            LevelCurve::Legacy => self.legacy_tier() as u32 + 1,
+        }
+    }
+
+    /// How far the falling piece drops on each frame, in cells times [`Level::CELL`], so that
+    /// `Level::CELL / 60` falls one row per second and `20 * Level::CELL` reaches the floor at
+    /// once.
+    pub const fn gravity(&self) -> u32 {
+        match self.leveling.curve {
+            LevelCurve::Guideline => {
+                let level = self.number() as usize;
+                if level < Level::GRAVITY.len() {
+                    Level::GRAVITY[level - 1]
+                } else {
+                    Level::GRAVITY[Level::GRAVITY.len() - 1]
+                }
+            }
+            LevelCurve::Legacy => Level::CELL / (self.tick_rate + 1),
+        }
+    }
+
+    /// How the level rises.
+    pub const fn leveling(&self) -> Leveling {
+        self.leveling
+    }
+
+    /// Counts a newly spawned piece and speeds the game up accordingly.
+    pub const fn update(&mut self) {
+        self.piece_count += 1;
+        self.tick_rate = Level::TIERS[self.legacy_tier()].1;
+    }
+
+    /// Counts rows cleared by the last piece, which may raise the level.
+    pub const fn add_lines(&mut self, lines: u32) {
+        self.lines += lines;
+    }
+
+    const fn legacy_tier(&self) -> usize {
+        let mut tier = 0;
+        while self.piece_count > Level::TIERS[tier].0 {
+            tier += 1;
+        }
+        tier
+    }
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level::new()
+    }
+}
+
+/// How the level of a game rises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Leveling {
+    /// How the level rises, and how fast pieces fall at each level.
+    pub curve: LevelCurve,
+    /// Rows to clear for each level with [`LevelCurve::Guideline`]. With `0`, the game stays on
+    /// the first level.
+    pub lines_per_level: u32,
+}
+
+impl Leveling {
+    /// The default leveling: the guideline curve, with a level every 10 lines.
+    pub const DEFAULT: Leveling = Leveling {
+        curve: LevelCurve::Guideline,
+        lines_per_level: 10,
+    };
+}
+
+impl Default for Leveling {
+    fn default() -> Self {
+        Leveling::DEFAULT
+    }
+}
+
+/// The speed curve of a game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LevelCurve {
+    /// A level every [`Leveling::lines_per_level`] rows cleared, with the gravity of the
+    /// guideline, which reaches 20 rows per frame on level 20.
+    #[default]
+    Guideline,
+    /// The original curve of this game: a level at set piece counts, with pieces falling one
+    /// row every [`Level::tick_rate`] frames.
+    Legacy,
+}
+
+impl FromStr for LevelCurve {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "guideline" => Ok(LevelCurve::Guideline),
+            "legacy" => Ok(LevelCurve::Legacy),
+            _ => Err(ParseSettingError {
+                expected: &["guideline", "legacy"],
+            }),
+        }
+    }
+}
+
+impl fmt::Display for LevelCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LevelCurve::Guideline => "guideline",
+            LevelCurve::Legacy => "legacy",
+        })
+    }
+}
+
+/// How long a piece resting on the stack waits before it locks, counted in frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockDelay {
+    /// Frames a grounded piece waits before locking. With `0`, pieces lock as soon as they land.
+    pub frames: u32,
+    /// What restarts the wait.
+    pub reset: LockReset,
+    /// How many times moving or turning a piece may restart the wait with
+    /// [`LockReset::MoveReset`].
+    pub max_resets: u32,
+}
+
+impl LockDelay {
+    /// The default lock delay: half a second, restarted by up to 15 moves per piece.
+    pub const DEFAULT: LockDelay = LockDelay {
+        frames: 30,
+        reset: LockReset::MoveReset,
+        max_resets: 15,
+    };
+}
+
+impl Default for LockDelay {
+    fn default() -> Self {
+        LockDelay::DEFAULT
+    }
+}
+
+/// What restarts the [`LockDelay`] of a grounded piece.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockReset {
+    /// Every move and turn restarts the wait, without limit.
+    Infinity,
+    /// Moves and turns restart the wait up to [`LockDelay::max_resets`] times, and the count
+    /// starts over whenever the piece falls below the lowest row it reached.
+    #[default]
+    MoveReset,
+    /// Only falling below the lowest row the piece reached restarts the wait.
+    StepReset,
+}
+
+impl FromStr for LockReset {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "infinity" => Ok(LockReset::Infinity),
+            "move_reset" => Ok(LockReset::MoveReset),
+            "step_reset" => Ok(LockReset::StepReset),
+            _ => Err(ParseSettingError {
+                expected: &["infinity", "move_reset", "step_reset"],
+            }),
+        }
+    }
+}
+
+impl fmt::Display for LockReset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LockReset::Infinity => "infinity",
+            LockReset::MoveReset => "move_reset",
+            LockReset::StepReset => "step_reset",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::playing;
+    use crate::InputFrame;
+
+    fn guideline(lines_per_level: u32) -> Level {
+        Level::with_leveling(Leveling {
+            curve: LevelCurve::Guideline,
+            lines_per_level,
+        })
+    }
+
+    #[test]
+    fn levels_up_by_lines() {
+        let mut level = guideline(10);
+        level.add_lines(9);
+        assert_eq!(level.number(), 1);
+        level.add_lines(1);
+        assert_eq!(level.number(), 2);
+        level.add_lines(25);
+        assert_eq!(level.number(), 4);
+    }
+
+    #[test]
+    fn no_lines_per_level_stays_on_the_first_level() {
+        let mut level = guideline(0);
+        level.add_lines(1000);
+        assert_eq!(level.number(), 1);
+        assert_eq!(level.gravity(), Level::GRAVITY[0]);
+    }
+
+    #[test]
+    fn legacy_levels_up_by_pieces() {
+        let mut level = Level::with_leveling(Leveling {
+            curve: LevelCurve::Legacy,
+            lines_per_level: 10,
+        });
+        level.add_lines(100);
+        assert_eq!((level.number(), level.tick_rate), (1, 30));
+        for _ in 0..25 {
+            level.update();
+        }
+        assert_eq!((level.number(), level.tick_rate), (2, 25));
+        for _ in 0..1000 {
+            level.update();
+        }
+        assert_eq!((level.number(), level.tick_rate), (9, 5));
+    }
+
+    #[test]
+    fn gravity_follows_the_guideline() {
+        let frames_per_row = |level: usize| Level::CELL.div_ceil(Level::GRAVITY[level - 1]);
+        assert_eq!(frames_per_row(1), 60);
+        assert_eq!(frames_per_row(2), 48);
+        assert!(Level::GRAVITY
+            .windows(2)
+            .all(|pair| pair[0] < pair[1] || pair[1] == 20 * Level::CELL));
+        assert_eq!(Level::GRAVITY[19], 20 * Level::CELL);
+
+        let mut level = guideline(1);
+        level.add_lines(30);
+        assert_eq!(level.gravity(), 20 * Level::CELL);
+    }
+
+    #[test]
+    fn level_one_drops_a_row_every_60_frames() {
+        let mut game = playing();
+        let y = game.position().1;
+        for _ in 0..59 {
+            game.update(InputFrame::IDLE);
+        }
+        assert_eq!(game.position().1, y);
+        game.update(InputFrame::IDLE);
+        assert_eq!(game.position().1, y + 1);
+    }
+
+    #[test]
+    fn level_twenty_drops_to_the_floor_at_once() {
+        let mut game = playing();
+        game.level.add_lines(190);
+        assert_eq!(game.level().number(), 20);
+        game.update(InputFrame::IDLE);
+        let bottom = game
+            .piece()
+            .neighbors(game.rotation())
+            .map(|(_, dy)| game.position().1 + dy);
+        assert!(bottom.contains(&(crate::Grid::HEIGHT as i8 - 1)));
+    }
+}
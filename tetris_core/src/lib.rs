@@ -0,0 +1,60 @@
+//! The Tetris engine shared by `tetris_macro::run!` and the build script example.
+//!
+//! The game rules ([`Game`], [`Grid`], [`Tetromino`], [`Rotation`] and [`Level`]) run headless:
+//! every frame, [`Game::update`] receives an [`InputFrame`] describing what the player pressed and
+//! held, and returns the [`Changes`] it made. Nothing in them depends on a window, so they can be
+//! driven from tests, bots or recorded inputs. They are all `const fn`s, so [`simulate`] can even
+//! play a game during constant evaluation.
+//!
+//! With the default `window` feature, [`run_tetris`] opens a window, lets the developer play a
+//! session and returns the final score. Any build script or procedural macro can call it:
+//!
+//! ```no_run
+//! # #[cfg(feature = "window")] {
+//! let score = tetris_core::run_tetris();
+//! println!("cargo::warning=You scored {score} points");
+//! # }
+//! ```
+
+mod bot;
+mod cache;
+mod controller;
+mod game;
+mod grid;
+mod gym;
+mod handling;
+mod input;
+mod json;
+mod level;
+mod lock;
+mod randomizer;
+mod replay;
+mod rng;
+mod rotation;
+mod scoring;
+mod settings;
+mod summary;
+mod tetromino;
+#[cfg(feature = "window")]
+mod window;
+
+pub use bot::{run_bot, Bot};
+pub use cache::{target_dir, tetris_dir, ResultCache};
+pub use controller::{run_headless, Controller, Playback};
+pub use game::{Changes, Game, State};
+pub use grid::{ClearedRows, Grid};
+pub use gym::Gym;
+pub use handling::Handling;
+pub use input::{Action, Actions, InputFrame};
+pub use level::{Level, LevelCurve, Leveling, LockDelay, LockReset};
+pub use lock::BuildLock;
+pub use randomizer::{Randomizer, RandomizerKind, SevenBag, TgmHistory, Uniform};
+pub use replay::{simulate, simulate_with, ParseReplayError, Replay};
+pub use rng::SplitMix64;
+pub use rotation::RotationSystem;
+pub use scoring::{Guideline, Legacy, LockEvent, ScoringKind, ScoringRule, TSpin};
+pub use settings::{Mode, ParseSettingError, Player, Settings};
+pub use summary::Summary;
+pub use tetromino::{Rotation, Tetromino};
+#[cfg(feature = "window")]
+pub use window::{run_tetris, run_tetris_recorded, run_tetris_shared, run_tetris_with, Keyboard};
@@ -0,0 +1,95 @@
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::PathBuf;
+
+use crate::{tetris_dir, Summary};
+
+/// Exclusive access to the game session of the current `cargo build`.
+///
+/// Cargo runs build scripts and rustc in parallel, so several crates may want to play at the
+/// same time. Each of them takes this lock first: the first one plays and records its result,
+/// the others wait for the lock and then find that result already recorded for their build.
+///
+/// The lock is an OS file lock on `<target>/tetris/session.lock`, released when the value is
+/// dropped or the process exits, so a crashed session never blocks the next build.
+#[derive(Debug)]
+pub struct BuildLock {
+    _file: File,
+    build: Option<String>,
+    path: PathBuf,
+}
+
+impl BuildLock {
+    /// Blocks until no other process of any build holds the lock.
+    ///
+    /// Calls `on_wait` once before blocking if another process is playing, so the caller can
+    /// explain why it hangs.
+    pub fn acquire(on_wait: impl FnOnce()) -> io::Result<BuildLock> {
+        let dir = tetris_dir();
+        fs::create_dir_all(&dir)?;
+        let file = File::create(dir.join("session.lock"))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                on_wait();
+                file.lock()?;
+            }
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+        Ok(BuildLock {
+            _file: file,
+            build: build_id(),
+            path: dir.join("session.txt"),
+        })
+    }
+
+    /// The result of the session already played during this build, if any.
+    pub fn result(&self) -> Option<Summary> {
+        let build = self.build.as_deref()?;
+        let text = fs::read_to_string(&self.path).ok()?;
+        let (recorded, summary) = text.split_once('\n')?;
+        if recorded.strip_prefix("build=")? != build {
+            return None;
+        }
+        Summary::decode(summary)
+    }
+
+    /// Records the result of the session played during this build, for the processes waiting
+    /// on the lock.
+    pub fn record(&self, summary: &Summary) -> io::Result<()> {
+        let Some(build) = &self.build else {
+            return Ok(());
+        };
+        fs::write(&self.path, format!("build={build}\n{}", summary.encode()))
+    }
+}
+
+/// Identifies the running Cargo invocation, which is the parent process of both rustc, where
+/// procedural macros run, and build scripts.
+///
+/// The process ID alone may be reused by a later build, so it is paired with the start time of
+/// the process. Returns `None` where the platform does not tell that time, in which case every
+/// process plays its own session, one after the other.
+fn build_id() -> Option<String> {
+    #[cfg(unix)]
+    {
+        let pid = std::os::unix::process::parent_id();
+        Some(format!("{pid}-{}", start_time(pid)?))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// The time process `pid` started at, in clock ticks since boot, as found in the `starttime`
+/// field of `/proc/<pid>/stat`. Unlike the times of the `/proc/<pid>` directory, it never
+/// changes while the process runs.
+#[cfg(unix)]
+fn start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // the command name, second field, is in parentheses and may itself hold spaces or
+    // parentheses, so fields are counted from the last closing one, which ends it
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(22 - 3)?.parse().ok()
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{ParseSettingError, SplitMix64, Tetromino};
+
+/// Decides the order in which pieces are dealt.
+///
+/// A [`Game`](crate::Game) deals with the randomizer picked by its [`RandomizerKind`], through
+/// the very `const` code these implementations forward to, since trait methods cannot be called
+/// in `const` code. It is not an extension point.
+pub trait Randomizer {
+    /// Draws the next piece, taking randomness from `rng`.
+    fn next(&mut self, rng: &mut SplitMix64) -> Tetromino;
+}
+
+/// Which [`Randomizer`] a game deals its pieces with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RandomizerKind {
+    /// [`SevenBag`], the guideline randomizer.
+    #[default]
+    SevenBag,
+    /// [`TgmHistory`], the randomizer of The Grand Master.
+    Tgm,
+    /// [`Uniform`], the original behaviour of this game.
+    Uniform,
+}
+
+impl FromStr for RandomizerKind {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "seven_bag" => Ok(RandomizerKind::SevenBag),
+            "tgm" => Ok(RandomizerKind::Tgm),
+            "uniform" => Ok(RandomizerKind::Uniform),
+            _ => Err(ParseSettingError {
+                expected: &["seven_bag", "tgm", "uniform"],
+            }),
+        }
+    }
+}
+
+impl fmt::Display for RandomizerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RandomizerKind::SevenBag => "seven_bag",
+            RandomizerKind::Tgm => "tgm",
+            RandomizerKind::Uniform => "uniform",
+        })
+    }
+}
+
+/// Every piece is picked independently and uniformly, which allows long droughts and floods.
+#[derive(Debug, Clone, Default)]
+pub struct Uniform;
+
+impl Uniform {
+    const fn deal(&mut self, rng: &mut SplitMix64) -> Tetromino {
+        any_piece(rng)
+    }
+}
+
+impl Randomizer for Uniform {
+    fn next(&mut self, rng: &mut SplitMix64) -> Tetromino {
+        self.deal(rng)
+    }
+}
+
+/// Deals the seven pieces in a random order, then shuffles them again.
+///
+/// The same piece never comes back more than 12 pieces later, and never more than twice in a row.
+#[derive(Debug, Clone)]
+pub struct SevenBag {
+    bag: [Tetromino; 7],
+    /// The pieces left to deal, at the start of `bag`.
+    len: usize,
+}
+
+impl SevenBag {
+    const fn new() -> SevenBag {
+        SevenBag {
+            bag: Tetromino::ALL,
+            len: 0,
+        }
+    }
+
+    const fn deal(&mut self, rng: &mut SplitMix64) -> Tetromino {
+        if self.len == 0 {
+            self.bag = Tetromino::ALL;
+            // a Fisher-Yates shuffle
+            let mut i = self.bag.len() - 1;
+            while i > 0 {
+                self.bag.swap(i, rng.below(i as u32 + 1) as usize);
+                i -= 1;
+            }
+            self.len = self.bag.len();
+        }
+        self.len -= 1;
+        self.bag[self.len]
+    }
+}
+
+impl Default for SevenBag {
+    fn default() -> Self {
+        SevenBag::new()
+    }
+}
+
+impl Randomizer for SevenBag {
+    fn next(&mut self, rng: &mut SplitMix64) -> Tetromino {
+        self.deal(rng)
+    }
+}
+
+/// Remembers the last four pieces and rolls up to four times for one that is not among them.
+///
+/// The first piece is never an S, Z or O, so that the game never starts with an overhang.
+#[derive(Debug, Clone)]
+pub struct TgmHistory {
+    history: [Tetromino; 4],
+    first: bool,
+}
+
+impl TgmHistory {
+    const ROLLS: usize = 4;
+    const FIRST: [Tetromino; 4] = [Tetromino::I, Tetromino::T, Tetromino::J, Tetromino::L];
+
+    const fn new() -> TgmHistory {
+        TgmHistory {
+            history: [Tetromino::Z; 4],
+            first: true,
+        }
+    }
+
+    const fn deal(&mut self, rng: &mut SplitMix64) -> Tetromino {
+        let tetromino = if self.first {
+            self.first = false;
+            TgmHistory::FIRST[rng.below(TgmHistory::FIRST.len() as u32) as usize]
+        } else {
+            let mut tetromino = any_piece(rng);
+            let mut roll = 1;
+            while roll < TgmHistory::ROLLS && self.remembers(tetromino) {
+                tetromino = any_piece(rng);
+                roll += 1;
+            }
+            tetromino
+        };
+        self.push(tetromino);
+        tetromino
+    }
+
+    const fn remembers(&self, tetromino: Tetromino) -> bool {
+        let mut i = 0;
+        while i < self.history.len() {
+            if self.history[i] as u8 == tetromino as u8 {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+
+    const fn push(&mut self, tetromino: Tetromino) {
+        let mut i = self.history.len() - 1;
+        while i > 0 {
+            self.history[i] = self.history[i - 1];
+            i -= 1;
+        }
+        self.history[0] = tetromino;
+    }
+}
+
+impl Default for TgmHistory {
+    fn default() -> Self {
+        TgmHistory::new()
+    }
+}
+
+impl Randomizer for TgmHistory {
+    fn next(&mut self, rng: &mut SplitMix64) -> Tetromino {
+        self.deal(rng)
+    }
+}
+
+/// The randomizer picked by a [`RandomizerKind`], along with its state.
+///
+/// Unlike the [`Randomizer`] trait, it deals in `const` code.
+#[derive(Debug, Clone)]
+pub(crate) enum AnyRandomizer {
+    SevenBag(SevenBag),
+    Tgm(TgmHistory),
+    Uniform(Uniform),
+}
+
+impl AnyRandomizer {
+    pub(crate) const fn new(kind: RandomizerKind) -> AnyRandomizer {
+        match kind {
+            RandomizerKind::SevenBag => AnyRandomizer::SevenBag(SevenBag::new()),
+            RandomizerKind::Tgm => AnyRandomizer::Tgm(TgmHistory::new()),
+            RandomizerKind::Uniform => AnyRandomizer::Uniform(Uniform),
+        }
+    }
+
+    pub(crate) const fn deal(&mut self, rng: &mut SplitMix64) -> Tetromino {
+        match self {
+            AnyRandomizer::SevenBag(randomizer) => randomizer.deal(rng),
+            AnyRandomizer::Tgm(randomizer) => randomizer.deal(rng),
+            AnyRandomizer::Uniform(randomizer) => randomizer.deal(rng),
+        }
+    }
+}
+
+/// Draws any of the seven pieces, each as likely.
+const fn any_piece(rng: &mut SplitMix64) -> Tetromino {
+    Tetromino::ALL[rng.below(Tetromino::ALL.len() as u32) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seven_bag_deals_every_piece_once_per_bag() {
+        let mut rng = SplitMix64::new(3);
+        let mut randomizer = SevenBag::default();
+        for _ in 0..10 {
+            let mut bag: Vec<_> = (0..7).map(|_| randomizer.next(&mut rng) as u8).collect();
+            bag.sort();
+            assert_eq!(bag, [0, 1, 2, 3, 4, 5, 6]);
+        }
+    }
+
+    #[test]
+    fn tgm_never_starts_with_an_overhang() {
+        for seed in 0..100 {
+            let first = TgmHistory::default().next(&mut SplitMix64::new(seed));
+            assert!(
+                TgmHistory::FIRST.contains(&first),
+                "{first:?} with seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn trait_deals_like_the_game() {
+        let mut game = crate::Game::with_seed(crate::Settings::DEFAULT, 9);
+        let mut rng = SplitMix64::new(9);
+        let mut randomizer = SevenBag::default();
+        assert_eq!(randomizer.next(&mut rng), game.piece());
+        assert_eq!(randomizer.next(&mut rng), game.next_piece());
+        game.update(crate::InputFrame::press(crate::Action::Confirm));
+        game.update(crate::InputFrame::press(crate::Action::HardDrop));
+        assert_eq!(randomizer.next(&mut rng), game.next_piece());
+    }
+}
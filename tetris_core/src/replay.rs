@@ -0,0 +1,324 @@
+use std::path::Path;
+use std::{error, fmt, fs, io};
+
+use crate::{Actions, Changes, Game, InputFrame, Settings, State};
+
+/// The settings and every input of one game, enough to play it again frame by frame.
+///
+/// Games are deterministic for a given seed, so [`Replay::play`] reaches the very same score as
+/// the recorded game, which makes the score auditable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    /// The settings of the game, always with its seed.
+    pub settings: Settings,
+    /// What the player did on each call to [`Game::update`], in order.
+    pub inputs: Vec<InputFrame>,
+}
+
+impl Replay {
+    /// Version of the file format written by [`Replay::encode`]. It changes whenever the same
+    /// inputs would play a different game, so that old replays are rejected rather than reaching
+    /// another score.
+    pub const VERSION: u32 = 10;
+    /// Extension of replay files.
+    pub const EXTENSION: &'static str = "ttr";
+
+    const MAGIC: &'static str = "tetris-replay";
+    /// Most frames a replay may hold, about three days of play at 60 frames per second, so that
+    /// a corrupt file cannot exhaust the memory.
+    const MAX_FRAMES: usize = 1 << 24;
+
+    /// Starts recording `game`, which should not have been updated yet.
+    pub fn new(game: &Game) -> Replay {
+        Replay {
+            settings: Settings {
+                seed: Some(game.seed()),
+                ..game.settings()
+            },
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Appends the input of one frame.
+    pub fn record(&mut self, input: InputFrame) {
+        self.inputs.push(input);
+    }
+
+    /// Updates `game` with `input` and records it.
+    ///
+    /// When the update restarts the game after a game over, the replay starts over with the new
+    /// game, so that it always holds the last game of a session.
+    pub fn update(&mut self, game: &mut Game, input: InputFrame) -> Changes {
+        let restarting = game.state == State::Over;
+        let changes = game.update(input);
+        if restarting && changes.state == Some(State::Play) {
+            // the frame that confirmed the restart takes a new game from the start screen to
+            // play as well, so it begins the new replay
+            *self = Replay::new(game);
+        }
+        self.record(input);
+        changes
+    }
+
+    /// Plays the recorded inputs on a new game and returns it as it stands after the last one,
+    /// or once the window was closed.
+    pub fn play(&self) -> Game {
+        let seed = self.settings.seed.unwrap_or_default();
+        simulate_with(self.settings, &self.inputs, seed)
+    }
+
+    /// Writes the replay in its text format: a version line, the settings as `key=value` lines,
+    /// then one `<count> <pressed> <held>` line per run of identical frames, with the actions as
+    /// hexadecimal bit masks.
+    pub fn encode(&self) -> String {
+        let settings = self.settings;
+        let mut text = format!(
+            "{} {}\nseed={}\nmode={}\nrotation={}\nrandomizer={}\nscoring={}\n\
+             level_curve={}\nlines_per_level={}\ndas={}\narr={}\nsoft_drop_factor={}\n\
+             lock_delay={}\nlock_reset={}\nlock_resets={}\nframes={}\ninputs:\n",
+            Replay::MAGIC,
+            Replay::VERSION,
+            settings.seed.unwrap_or_default(),
+            settings.mode,
+            settings.rotation_system,
+            settings.randomizer,
+            settings.scoring,
+            settings.leveling.curve,
+            settings.leveling.lines_per_level,
+            settings.handling.das,
+            settings.handling.arr,
+            settings.handling.soft_drop_factor,
+            settings.lock_delay.frames,
+            settings.lock_delay.reset,
+            settings.lock_delay.max_resets,
+            self.inputs.len(),
+        );
+        let mut inputs = self.inputs.iter().peekable();
+        while let Some(&input) = inputs.next() {
+            let mut count = 1;
+            while inputs.next_if_eq(&&input).is_some() {
+                count += 1;
+            }
+            text += &format!(
+                "{count} {:04x} {:04x}\n",
+                input.pressed.bits(),
+                input.held.bits()
+            );
+        }
+        text
+    }
+
+    /// Reads a replay written by [`Replay::encode`].
+    pub fn decode(text: &str) -> Result<Replay, ParseReplayError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+        let error = |line, message: &str| ParseReplayError {
+            line,
+            message: message.to_owned(),
+        };
+
+        let (line, header) = lines.next().ok_or_else(|| error(1, "the file is empty"))?;
+        let version = header
+            .strip_prefix(Replay::MAGIC)
+            .and_then(|version| version.trim().parse::<u32>().ok())
+            .ok_or_else(|| error(line, "not a replay file"))?;
+        if version != Replay::VERSION {
+            let message = format!(
+                "version {version} is not supported, expected version {}",
+                Replay::VERSION
+            );
+            return Err(error(line, &message));
+        }
+
+        let mut settings = Settings::default();
+        let mut frames = None;
+        for (line, text) in &mut lines {
+            if text == "inputs:" {
+                break;
+            }
+            let (key, value) = text
+                .split_once('=')
+                .ok_or_else(|| error(line, "expected `key=value`"))?;
+            let invalid = || error(line, &format!("invalid value for `{key}`"));
+            match key {
+                "seed" => settings.seed = Some(value.parse().map_err(|_| invalid())?),
+                "mode" => settings.mode = value.parse().map_err(|_| invalid())?,
+                "rotation" => settings.rotation_system = value.parse().map_err(|_| invalid())?,
+                "randomizer" => settings.randomizer = value.parse().map_err(|_| invalid())?,
+                "scoring" => settings.scoring = value.parse().map_err(|_| invalid())?,
+                "level_curve" => settings.leveling.curve = value.parse().map_err(|_| invalid())?,
+                "lines_per_level" => {
+                    settings.leveling.lines_per_level = value.parse().map_err(|_| invalid())?
+                }
+                "das" => settings.handling.das = value.parse().map_err(|_| invalid())?,
+                "arr" => settings.handling.arr = value.parse().map_err(|_| invalid())?,
+                "soft_drop_factor" => {
+                    settings.handling.soft_drop_factor = value.parse().map_err(|_| invalid())?
+                }
+                "lock_delay" => {
+                    settings.lock_delay.frames = value.parse().map_err(|_| invalid())?
+                }
+                "lock_reset" => settings.lock_delay.reset = value.parse().map_err(|_| invalid())?,
+                "lock_resets" => {
+                    settings.lock_delay.max_resets = value.parse().map_err(|_| invalid())?
+                }
+                "frames" => {
+                    let value = value.parse::<usize>().map_err(|_| invalid())?;
+                    if value > Replay::MAX_FRAMES {
+                        return Err(error(line, "the replay is too long"));
+                    }
+                    frames = Some(value);
+                }
+                // keys added by later versions of the same format
+                _ => {}
+            }
+        }
+        if settings.seed.is_none() {
+            return Err(error(line, "the seed is missing"));
+        }
+
+        let limit = frames.unwrap_or(Replay::MAX_FRAMES);
+        let mut inputs = Vec::with_capacity(limit.min(1 << 16));
+        let mut last_line = line;
+        for (line, text) in lines {
+            last_line = line;
+            let malformed = || error(line, "expected `<count> <pressed> <held>`");
+            let mut fields = text.split(' ');
+            let count: usize = fields
+                .next()
+                .and_then(|count| count.parse().ok())
+                .ok_or_else(malformed)?;
+            let mut actions = || {
+                fields
+                    .next()
+                    .and_then(|bits| u16::from_str_radix(bits, 16).ok())
+                    .map(Actions::from_bits)
+                    .ok_or_else(malformed)
+            };
+            let input = InputFrame {
+                pressed: actions()?,
+                held: actions()?,
+            };
+            if count > limit - inputs.len() {
+                return Err(match frames {
+                    Some(_) => error(line, "the number of frames does not match"),
+                    None => error(line, "the replay is too long"),
+                });
+            }
+            inputs.extend((0..count).map(|_| input));
+        }
+        if frames.is_some_and(|frames| frames != inputs.len()) {
+            return Err(error(last_line, "the number of frames does not match"));
+        }
+        Ok(Replay { settings, inputs })
+    }
+
+    /// Reads the replay stored at `path`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Replay> {
+        let text = fs::read_to_string(path)?;
+        Replay::decode(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Stores the replay at `path`, creating its directory if needed.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.encode())
+    }
+}
+
+/// Plays `inputs` on a new game with the default [`Settings`] and pieces drawn from `seed`, and
+/// returns the final score.
+///
+/// The whole engine runs in `const` code, so scores can be computed at compile time without a
+/// procedural macro or a build script:
+///
+/// ```
+/// use tetris_core::{Action, InputFrame};
+///
+/// const INPUTS: [InputFrame; 3] = [
+///     InputFrame::press(Action::Confirm),
+///     InputFrame::IDLE,
+///     InputFrame::press(Action::HardDrop),
+/// ];
+/// const SCORE: u32 = tetris_core::simulate(&INPUTS, 1234);
+/// assert_eq!(SCORE, 0);
+/// ```
+pub const fn simulate(inputs: &[InputFrame], seed: u64) -> u32 {
+    simulate_with(Settings::DEFAULT, inputs, seed).score()
+}
+
+/// Same as [`simulate`], but plays with `settings` and returns the whole game as it stands after
+/// the last input, or once the window was closed. The seed in `settings` is ignored.
+pub const fn simulate_with(settings: Settings, inputs: &[InputFrame], seed: u64) -> Game {
+    let mut game = Game::with_seed(settings, seed);
+    let mut i = 0;
+    while i < inputs.len() && !matches!(game.state, State::WindowClose) {
+        game.update(inputs[i]);
+        i += 1;
+    }
+    game
+}
+
+/// Error returned when a replay file is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseReplayError {
+    line: usize,
+    message: String,
+}
+
+impl ParseReplayError {
+    /// The line of the file the error was found on, starting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl fmt::Display for ParseReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl error::Error for ParseReplayError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Action;
+
+    fn replay() -> Replay {
+        let mut game = Game::with_settings(Settings {
+            seed: Some(7),
+            ..Settings::default()
+        });
+        let mut replay = Replay::new(&game);
+        for input in [
+            InputFrame::press(Action::Confirm),
+            InputFrame::IDLE,
+            InputFrame::IDLE,
+            InputFrame::press(Action::HardDrop),
+        ] {
+            replay.update(&mut game, input);
+        }
+        replay
+    }
+
+    #[test]
+    fn round_trips() {
+        let replay = replay();
+        assert_eq!(Replay::decode(&replay.encode()), Ok(replay));
+    }
+
+    #[test]
+    fn rejects_corrupt_lengths() {
+        let text = replay().encode();
+        let huge = text.replace("frames=4", &format!("frames={}", usize::MAX));
+        assert_eq!(Replay::decode(&huge).unwrap_err().line(), 15);
+        let long_run = text.replace("\n2 0000", &format!("\n{} 0000", usize::MAX));
+        assert!(Replay::decode(&long_run).is_err());
+        let unannounced = long_run.replace("frames=4\n", "");
+        assert!(Replay::decode(&unannounced).is_err());
+    }
+}
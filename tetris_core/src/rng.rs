@@ -0,0 +1,63 @@
+/// The seedable random number generator every [`Game`](crate::Game) draws its pieces from.
+///
+/// Its output is fixed by this crate rather than by `rand`, so a seed deals the same pieces on
+/// every machine, whatever version of `rand` ends up in the lock file.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// Creates a generator whose output is entirely determined by `seed`.
+    pub const fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    /// Returns the next 64 random bits.
+    pub const fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns the next 32 random bits, as [`rand::RngCore::next_u32`] does.
+    pub(crate) const fn next_u32(&mut self) -> u32 {
+        (self.next() >> 32) as u32
+    }
+
+    /// Draws a number below `len`, each as likely.
+    pub(crate) const fn below(&mut self, len: u32) -> u32 {
+        // Lemire's multiply-and-shift, retrying the few draws that would favour small numbers
+        let threshold = len.wrapping_neg() % len;
+        loop {
+            let wide = self.next_u32() as u64 * len as u64;
+            if wide as u32 >= threshold {
+                return (wide >> 32) as u32;
+            }
+        }
+    }
+}
+
+impl rand::RngCore for SplitMix64 {
+    fn next_u32(&mut self) -> u32 {
+        SplitMix64::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.next()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
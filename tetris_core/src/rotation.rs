@@ -0,0 +1,172 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{ParseSettingError, Rotation, Tetromino};
+
+/// Decides where a piece may land when a rotation is blocked in place.
+///
+/// When a piece rotates, the engine tries each offset returned by
+/// [`RotationSystem::kicks`] in order and keeps the first one that fits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RotationSystem {
+    /// The Super Rotation System of the Tetris Guideline, with per-piece wall and floor kicks.
+    #[default]
+    Srs,
+    /// The original behaviour of this game: only horizontal kicks of up to two columns.
+    Classic,
+}
+
+impl FromStr for RotationSystem {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "srs" => Ok(RotationSystem::Srs),
+            "classic" => Ok(RotationSystem::Classic),
+            _ => Err(ParseSettingError {
+                expected: &["srs", "classic"],
+            }),
+        }
+    }
+}
+
+impl fmt::Display for RotationSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RotationSystem::Srs => "srs",
+            RotationSystem::Classic => "classic",
+        })
+    }
+}
+
+// Offsets are written as (x, y) with y growing downwards, i.e. the SRS tables with y negated.
+const JLSTZ_0_R: [(i8, i8); 5] = [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)];
+const JLSTZ_R_0: [(i8, i8); 5] = [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)];
+const JLSTZ_R_2: [(i8, i8); 5] = [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)];
+const JLSTZ_2_R: [(i8, i8); 5] = [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)];
+const JLSTZ_2_L: [(i8, i8); 5] = [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)];
+const JLSTZ_L_2: [(i8, i8); 5] = [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)];
+const JLSTZ_L_0: [(i8, i8); 5] = [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)];
+const JLSTZ_0_L: [(i8, i8); 5] = [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)];
+
+const I_0_R: [(i8, i8); 5] = [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)];
+const I_R_0: [(i8, i8); 5] = [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)];
+const I_R_2: [(i8, i8); 5] = [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)];
+const I_2_R: [(i8, i8); 5] = [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)];
+const I_2_L: [(i8, i8); 5] = [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)];
+const I_L_2: [(i8, i8); 5] = [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)];
+const I_L_0: [(i8, i8); 5] = [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)];
+const I_0_L: [(i8, i8); 5] = [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)];
+
+const CLASSIC: [(i8, i8); 5] = [(0, 0), (-1, 0), (1, 0), (-2, 0), (2, 0)];
+
+impl RotationSystem {
+    /// The offsets to try, in order, when `tetromino` turns from `from` to `to`.
+    ///
+    /// `from` and `to` are expected to be a quarter turn apart.
+    pub const fn kicks(
+        self,
+        tetromino: Tetromino,
+        from: Rotation,
+        to: Rotation,
+    ) -> &'static [(i8, i8)] {
+        use Rotation::{DEG0, DEG180, DEG270, DEG90};
+        if let RotationSystem::Classic = self {
+            return &CLASSIC;
+        }
+        match (tetromino, from, to) {
+            (Tetromino::O, _, _) => &[(0, 0)],
+            (Tetromino::I, DEG0, DEG90) => &I_0_R,
+            (Tetromino::I, DEG90, DEG0) => &I_R_0,
+            (Tetromino::I, DEG90, DEG180) => &I_R_2,
+            (Tetromino::I, DEG180, DEG90) => &I_2_R,
+            (Tetromino::I, DEG180, DEG270) => &I_2_L,
+            (Tetromino::I, DEG270, DEG180) => &I_L_2,
+            (Tetromino::I, DEG270, DEG0) => &I_L_0,
+            (Tetromino::I, DEG0, DEG270) => &I_0_L,
+            (_, DEG0, DEG90) => &JLSTZ_0_R,
+            (_, DEG90, DEG0) => &JLSTZ_R_0,
+            (_, DEG90, DEG180) => &JLSTZ_R_2,
+            (_, DEG180, DEG90) => &JLSTZ_2_R,
+            (_, DEG180, DEG270) => &JLSTZ_2_L,
+            (_, DEG270, DEG180) => &JLSTZ_L_2,
+            (_, DEG270, DEG0) => &JLSTZ_L_0,
+            (_, DEG0, DEG270) => &JLSTZ_0_L,
+            _ => &[(0, 0)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::tests::with_piece;
+    use crate::{Action, InputFrame};
+
+    /// Converts offsets from the SRS tables as published, with y growing upwards.
+    fn srs(offsets: [(i8, i8); 5]) -> [(i8, i8); 5] {
+        offsets.map(|(x, y)| (x, -y))
+    }
+
+    #[test]
+    fn kick_tables_match_srs() {
+        use Rotation::{DEG0, DEG180, DEG270, DEG90};
+        let kicks = |tetromino, from, to| RotationSystem::Srs.kicks(tetromino, from, to).to_vec();
+        assert_eq!(
+            kicks(Tetromino::T, DEG0, DEG90),
+            srs([(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)])
+        );
+        assert_eq!(
+            kicks(Tetromino::L, DEG180, DEG270),
+            srs([(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)])
+        );
+        assert_eq!(
+            kicks(Tetromino::I, DEG0, DEG90),
+            srs([(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)])
+        );
+        assert_eq!(
+            kicks(Tetromino::I, DEG90, DEG180),
+            srs([(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)])
+        );
+    }
+
+    #[test]
+    fn i_piece_kicks_off_the_right_wall() {
+        // upright against the right wall, with no room to lie flat in place
+        let mut game = with_piece(Tetromino::I, (8, 10), Rotation::DEG90, &[]);
+        game.update(InputFrame::press(Action::RotateCw));
+        assert_eq!(game.rotation(), Rotation::DEG180);
+        assert_eq!(game.position(), (7, 10));
+    }
+
+    #[test]
+    fn t_piece_kicks_up_off_the_floor() {
+        let mut game = with_piece(Tetromino::T, (4, 21), Rotation::DEG0, &[]);
+        game.update(InputFrame::press(Action::RotateCw));
+        assert_eq!(game.rotation(), Rotation::DEG90);
+        assert_eq!(game.position(), (3, 20));
+    }
+
+    #[test]
+    fn t_piece_kicks_into_a_t_spin_triple() {
+        // three rows open only in the shape of an upright T pointing right, under an overhang
+        let mut filled = vec![(1, 17)];
+        for y in 19..22 {
+            filled.extend((0..10).map(|x| (x, y)));
+        }
+        filled.retain(|cell| ![(1, 19), (1, 20), (2, 20), (1, 21)].contains(cell));
+        let mut game = with_piece(Tetromino::T, (2, 18), Rotation::DEG0, &filled);
+
+        game.update(InputFrame::press(Action::RotateCw));
+        assert_eq!(game.rotation(), Rotation::DEG90);
+        assert_eq!(game.position(), (1, 20));
+        let changes = game.update(InputFrame::press(Action::HardDrop));
+        assert_eq!(changes.lines_cleared, 3);
+    }
+
+    #[test]
+    fn classic_only_kicks_sideways() {
+        let kicks = RotationSystem::Classic.kicks(Tetromino::T, Rotation::DEG0, Rotation::DEG90);
+        assert!(kicks.iter().all(|&(_, y)| y == 0));
+    }
+}
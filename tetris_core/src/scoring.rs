@@ -0,0 +1,349 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ParseSettingError;
+
+/// Decides how many points each locked piece earns.
+///
+/// A [`Game`](crate::Game) only scores with the rules of this crate, picked by its
+/// [`ScoringKind`], since it scores in `const` code and cannot call a rule of another type.
+/// The trait lets the built-in rules score a sequence of [`LockEvent`]s on their own.
+pub trait ScoringRule {
+    /// Returns the points earned by the piece described by `lock`, keeping track of streaks such
+    /// as combos along the way.
+    fn score(&mut self, lock: &LockEvent) -> u32;
+}
+
+/// What happened when a piece locked, as seen by a [`ScoringRule`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockEvent {
+    /// Number of rows the piece cleared.
+    pub lines: u8,
+    /// The level the piece was played on, starting at 1.
+    pub level: u32,
+    /// Rows the piece fell while soft drop was held.
+    pub soft_drop: u32,
+    /// Rows the piece fell when it was hard dropped.
+    pub hard_drop: u32,
+    /// The T-spin the piece made, if any.
+    pub t_spin: Option<TSpin>,
+    /// Whether the piece emptied the whole grid.
+    pub perfect_clear: bool,
+}
+
+/// A T piece turned into a spot it could not have moved into, told by the 3-corner rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TSpin {
+    /// Only one of the corners the T points to is filled.
+    Mini,
+    /// Both corners the T points to are filled, or the turn needed the last kick of SRS.
+    Full,
+}
+
+impl TSpin {
+    /// The name of the T-spin, such as `"T-spin"`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            TSpin::Mini => "mini T-spin",
+            TSpin::Full => "T-spin",
+        }
+    }
+}
+
+/// Which [`ScoringRule`] a game is scored with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScoringKind {
+    /// [`Legacy`], the original scoring of this game.
+    #[default]
+    Legacy,
+    /// [`Guideline`], the scoring of modern Tetris games.
+    Guideline,
+}
+
+impl FromStr for ScoringKind {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "legacy" => Ok(ScoringKind::Legacy),
+            "guideline" => Ok(ScoringKind::Guideline),
+            _ => Err(ParseSettingError {
+                expected: &["legacy", "guideline"],
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ScoringKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScoringKind::Legacy => "legacy",
+            ScoringKind::Guideline => "guideline",
+        })
+    }
+}
+
+/// 5, 15, 30 and 50 points for clearing one to four rows at once, whatever the level, with no
+/// bonus for T-spins or perfect clears.
+#[derive(Debug, Clone, Default)]
+pub struct Legacy;
+
+impl Legacy {
+    const fn award(&mut self, lock: &LockEvent) -> u32 {
+        match lock.lines {
+            0 => 0,
+            1 => 5,
+            2 => 15,
+            3 => 30,
+            _ => 50,
+        }
+    }
+}
+
+impl ScoringRule for Legacy {
+    fn score(&mut self, lock: &LockEvent) -> u32 {
+        self.award(lock)
+    }
+}
+
+/// The scoring of the Tetris guideline.
+///
+/// Clearing one to four rows earns 100, 300, 500 and 800 points times the level. A T-spin earns
+/// 400, 800, 1200 and 1600 points times the level for clearing zero to three rows, and a mini
+/// T-spin 100, 200 and 400 points times the level for clearing zero to two. Tetrises and T-spins
+/// that clear rows are difficult clears, which earn half as much again when they follow another
+/// one with no easier clear in between. Every clear in a row after the first adds 50 points
+/// times the combo and the level. Soft drop earns 1 point per row and hard drop 2.
+///
+/// A perfect clear adds 800, 1200, 1800 and 2000 points times the level for clearing one to four
+/// rows, or 3200 for a tetris following another difficult clear.
+#[derive(Debug, Clone, Default)]
+pub struct Guideline {
+    /// Clears in a row so far, minus one, or `None` when the last piece cleared nothing.
+    combo: Option<u32>,
+    /// Whether the last clear was a difficult one.
+    back_to_back: bool,
+}
+
+impl Guideline {
+    const fn new() -> Guideline {
+        Guideline {
+            combo: None,
+            back_to_back: false,
+        }
+    }
+
+    const fn award(&mut self, lock: &LockEvent) -> u32 {
+        let drops = lock.soft_drop + 2 * lock.hard_drop;
+        let base = match (lock.t_spin, lock.lines) {
+            (None, 0) => 0,
+            (None, 1) => 100,
+            (None, 2) => 300,
+            (None, 3) => 500,
+            (None, _) => 800,
+            (Some(TSpin::Mini), 0) => 100,
+            (Some(TSpin::Mini), 1) => 200,
+            (Some(TSpin::Mini), _) => 400,
+            (Some(TSpin::Full), 0) => 400,
+            (Some(TSpin::Full), 1) => 800,
+            (Some(TSpin::Full), 2) => 1200,
+            (Some(TSpin::Full), _) => 1600,
+        };
+        let mut points = base * lock.level;
+        if lock.lines == 0 {
+            self.combo = None;
+            return drops + points;
+        }
+
+        let difficult = lock.lines >= 4 || lock.t_spin.is_some();
+        if difficult && self.back_to_back {
+            points += points / 2;
+        }
+        if lock.perfect_clear {
+            points += match lock.lines {
+                1 => 800,
+                2 => 1200,
+                3 => 1800,
+                _ if self.back_to_back => 3200,
+                _ => 2000,
+            } * lock.level;
+        }
+        self.back_to_back = difficult;
+
+        let combo = match self.combo {
+            Some(combo) => combo + 1,
+            None => 0,
+        };
+        self.combo = Some(combo);
+        drops + points + 50 * combo * lock.level
+    }
+}
+
+impl ScoringRule for Guideline {
+    fn score(&mut self, lock: &LockEvent) -> u32 {
+        self.award(lock)
+    }
+}
+
+/// The scoring rule picked by a [`ScoringKind`], along with its state.
+///
+/// Unlike the [`ScoringRule`] trait, it scores in `const` code.
+#[derive(Debug, Clone)]
+pub(crate) enum AnyScoring {
+    Legacy(Legacy),
+    Guideline(Guideline),
+}
+
+impl AnyScoring {
+    pub(crate) const fn new(kind: ScoringKind) -> AnyScoring {
+        match kind {
+            ScoringKind::Legacy => AnyScoring::Legacy(Legacy),
+            ScoringKind::Guideline => AnyScoring::Guideline(Guideline::new()),
+        }
+    }
+
+    pub(crate) const fn award(&mut self, lock: &LockEvent) -> u32 {
+        match self {
+            AnyScoring::Legacy(rule) => rule.award(lock),
+            AnyScoring::Guideline(rule) => rule.award(lock),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear(lines: u8) -> LockEvent {
+        LockEvent {
+            lines,
+            level: 1,
+            ..LockEvent::default()
+        }
+    }
+
+    fn scores(rule: &mut impl ScoringRule, locks: &[LockEvent]) -> Vec<u32> {
+        locks.iter().map(|lock| rule.score(lock)).collect()
+    }
+
+    #[test]
+    fn legacy_ignores_level_and_bonuses() {
+        let tetris = LockEvent {
+            level: 9,
+            hard_drop: 20,
+            perfect_clear: true,
+            ..clear(4)
+        };
+        assert_eq!(
+            scores(
+                &mut Legacy,
+                &[clear(0), clear(1), clear(2), clear(3), tetris]
+            ),
+            [0, 5, 15, 30, 50]
+        );
+    }
+
+    #[test]
+    fn guideline_clears_scale_with_level() {
+        let at_level = |lines, level| LockEvent {
+            level,
+            ..clear(lines)
+        };
+        let mut rule = Guideline::default();
+        // a piece that clears nothing in between keeps combos out of the way
+        let locks = [
+            at_level(1, 3),
+            clear(0),
+            at_level(3, 2),
+            clear(0),
+            at_level(4, 5),
+        ];
+        assert_eq!(scores(&mut rule, &locks), [300, 0, 1000, 0, 4000]);
+    }
+
+    #[test]
+    fn guideline_drops_earn_points_per_row() {
+        let drops = LockEvent {
+            soft_drop: 5,
+            hard_drop: 10,
+            level: 4,
+            ..LockEvent::default()
+        };
+        assert_eq!(Guideline::default().score(&drops), 25);
+    }
+
+    #[test]
+    fn guideline_back_to_back() {
+        let mut rule = Guideline::default();
+        let locks = [
+            clear(4),
+            clear(0),
+            clear(4),
+            clear(0),
+            clear(1),
+            clear(0),
+            clear(4),
+        ];
+        assert_eq!(scores(&mut rule, &locks), [800, 0, 1200, 0, 100, 0, 800]);
+
+        let t_spin_single = LockEvent {
+            t_spin: Some(TSpin::Full),
+            ..clear(1)
+        };
+        let mut rule = Guideline::default();
+        let locks = [clear(4), clear(0), t_spin_single, clear(0), clear(4)];
+        assert_eq!(scores(&mut rule, &locks), [800, 0, 1200, 0, 1200]);
+    }
+
+    #[test]
+    fn guideline_combos() {
+        let mut rule = Guideline::default();
+        let locks = [clear(1), clear(1), clear(2), clear(1), clear(0), clear(1)];
+        assert_eq!(scores(&mut rule, &locks), [100, 150, 400, 250, 0, 100]);
+
+        let mut rule = Guideline::default();
+        let at_level_2 = LockEvent {
+            level: 2,
+            ..clear(1)
+        };
+        assert_eq!(scores(&mut rule, &[at_level_2, at_level_2]), [200, 300]);
+    }
+
+    #[test]
+    fn guideline_t_spins() {
+        let t_spin = |t_spin, lines| LockEvent {
+            t_spin: Some(t_spin),
+            ..clear(lines)
+        };
+        let mut rule = Guideline::default();
+        let locks = [
+            t_spin(TSpin::Mini, 0),
+            t_spin(TSpin::Full, 0),
+            t_spin(TSpin::Mini, 1),
+        ];
+        assert_eq!(scores(&mut rule, &locks), [100, 400, 200]);
+        assert_eq!(Guideline::default().score(&t_spin(TSpin::Full, 2)), 1200);
+    }
+
+    #[test]
+    fn guideline_perfect_clears() {
+        let perfect = |lines, level| LockEvent {
+            level,
+            perfect_clear: true,
+            ..clear(lines)
+        };
+        let mut rule = Guideline::default();
+        let locks = [
+            perfect(1, 1),
+            clear(0),
+            perfect(2, 2),
+            clear(0),
+            perfect(4, 1),
+        ];
+        assert_eq!(scores(&mut rule, &locks), [900, 0, 3000, 0, 2800]);
+
+        let mut rule = Guideline::default();
+        let locks = [clear(4), clear(0), perfect(4, 1)];
+        assert_eq!(scores(&mut rule, &locks), [800, 0, 4400]);
+    }
+}
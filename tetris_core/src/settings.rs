@@ -0,0 +1,164 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::{Handling, Leveling, LockDelay, RandomizerKind, RotationSystem, ScoringKind};
+
+/// How a session is played. The settings survive restarts after a game over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    pub mode: Mode,
+    pub rotation_system: RotationSystem,
+    pub randomizer: RandomizerKind,
+    /// How locked pieces are scored. T-spins and perfect clears only earn bonus points with
+    /// [`ScoringKind::Guideline`].
+    pub scoring: ScoringKind,
+    /// How the level rises and how fast pieces fall.
+    pub leveling: Leveling,
+    /// Seed of the piece sequence. Two games with the same seed and settings deal the same
+    /// pieces; `None` picks a fresh seed for every game.
+    pub seed: Option<u64>,
+    /// Wall-clock limit of the whole session. Once it elapses the window closes and the score
+    /// reached so far is kept. When the [`Player::Bot`] plays, the limit counts game time
+    /// instead, so that its results are reproducible.
+    pub timeout: Option<Duration>,
+    /// Who plays the session.
+    pub player: Player,
+    /// Ends the session once this many pieces are locked, keeping the score reached so far.
+    pub piece_limit: Option<u32>,
+    /// How held keys repeat and how fast soft drop falls.
+    pub handling: Handling,
+    /// How long landed pieces wait before locking.
+    pub lock_delay: LockDelay,
+}
+
+impl Settings {
+    /// The default settings, usable in `const` code: a marathon with SRS, the seven-bag
+    /// randomizer and the legacy scoring, played by a human with a fresh seed, no limit, and the
+    /// default [`Leveling`], [`Handling`] and [`LockDelay`].
+    pub const DEFAULT: Settings = Settings {
+        mode: Mode::Marathon,
+        rotation_system: RotationSystem::Srs,
+        randomizer: RandomizerKind::SevenBag,
+        scoring: ScoringKind::Legacy,
+        leveling: Leveling::DEFAULT,
+        seed: None,
+        timeout: None,
+        player: Player::Human,
+        piece_limit: None,
+        handling: Handling::DEFAULT,
+        lock_delay: LockDelay::DEFAULT,
+    };
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings::DEFAULT
+    }
+}
+
+/// The goal of a game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Play until the stack tops out.
+    #[default]
+    Marathon,
+    /// The game ends once [`Mode::SPRINT_LINES`] lines are cleared.
+    Sprint,
+    /// The game ends after [`Mode::ULTRA_FRAMES`] frames of play.
+    Ultra,
+}
+
+impl Mode {
+    /// Lines to clear in [`Mode::Sprint`].
+    pub const SPRINT_LINES: u32 = 40;
+    /// Frames of play in [`Mode::Ultra`], i.e. two minutes at 60 frames per second.
+    pub const ULTRA_FRAMES: u32 = 2 * 60 * 60;
+}
+
+impl FromStr for Mode {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "marathon" => Ok(Mode::Marathon),
+            "sprint" => Ok(Mode::Sprint),
+            "ultra" => Ok(Mode::Ultra),
+            _ => Err(ParseSettingError {
+                expected: &["marathon", "sprint", "ultra"],
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Mode::Marathon => "marathon",
+            Mode::Sprint => "sprint",
+            Mode::Ultra => "ultra",
+        })
+    }
+}
+
+/// Who plays a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Player {
+    /// The developer, in a window.
+    #[default]
+    Human,
+    /// The built-in [`Bot`](crate::Bot), without a window, so that builds finish on machines
+    /// without a display.
+    Bot,
+}
+
+impl FromStr for Player {
+    type Err = ParseSettingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Player::Human),
+            "bot" => Ok(Player::Bot),
+            _ => Err(ParseSettingError {
+                expected: &["human", "bot"],
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Player::Human => "human",
+            Player::Bot => "bot",
+        })
+    }
+}
+
+/// Error returned when a setting is parsed from a name it does not know.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSettingError {
+    pub(crate) expected: &'static [&'static str],
+}
+
+impl ParseSettingError {
+    /// The names the setting accepts.
+    pub fn expected(&self) -> &'static [&'static str] {
+        self.expected
+    }
+}
+
+impl fmt::Display for ParseSettingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected one of ")?;
+        for (i, name) in self.expected.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "`{name}`")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseSettingError {}
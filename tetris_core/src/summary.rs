@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use crate::{Game, Grid, Mode, Tetromino};
+
+/// The outcome of a session: the statistics of its last game and the final stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub score: u32,
+    pub lines: u32,
+    /// How many singles, doubles, triples and tetrises were cleared, in that order.
+    pub line_clears: [u32; 4],
+    /// How many times the whole grid was emptied.
+    pub perfect_clears: u32,
+    pub level: u32,
+    /// Pieces locked into the grid.
+    pub pieces: u32,
+    /// Time spent playing, counted in frames at 60 frames per second.
+    pub duration: Duration,
+    pub seed: u64,
+    pub mode: Mode,
+    /// The final grid, row by row from the top.
+    pub board: [[Option<Tetromino>; Grid::WIDTH as usize]; Grid::HEIGHT as usize],
+}
+
+impl Summary {
+    /// Frames per second of the game loop.
+    pub const FRAME_RATE: u32 = 60;
+
+    /// Takes the statistics of `game` as it currently stands.
+    pub fn of(game: &Game) -> Summary {
+        let mut board = [[None; Grid::WIDTH as usize]; Grid::HEIGHT as usize];
+        for (y, row) in board.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = *game.grid().at(x as u8, y as u8);
+            }
+        }
+        let frames = u64::from(game.frames());
+        Summary {
+            score: game.score(),
+            lines: game.lines(),
+            line_clears: game.line_clears(),
+            perfect_clears: game.perfect_clears(),
+            level: game.level().number(),
+            pieces: game.pieces(),
+            duration: Duration::from_millis(frames * 1000 / u64::from(Summary::FRAME_RATE)),
+            seed: game.seed(),
+            mode: game.settings().mode,
+            board,
+        }
+    }
+
+    /// The final grid as text, one string per row, with `.` for empty cells and the letter of
+    /// the piece for filled ones.
+    pub fn board_rows(&self) -> Vec<String> {
+        self.board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.map_or('.', Tetromino::to_char))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Writes the summary as `key=value` lines, one `board=` line per row, so that it can be
+    /// stored between builds and read back with [`Summary::decode`].
+    pub fn encode(&self) -> String {
+        let [singles, doubles, triples, tetrises] = self.line_clears;
+        let mut text = format!(
+            "score={}\nlines={}\nline_clears={singles},{doubles},{triples},{tetrises}\n\
+             perfect_clears={}\nlevel={}\npieces={}\nduration_ms={}\nseed={}\nmode={}\n",
+            self.score,
+            self.lines,
+            self.perfect_clears,
+            self.level,
+            self.pieces,
+            self.duration.as_millis(),
+            self.seed,
+            self.mode,
+        );
+        for row in self.board_rows() {
+            text += &format!("board={row}\n");
+        }
+        text
+    }
+
+    /// Reads a summary written by [`Summary::encode`], ignoring unknown keys.
+    ///
+    /// Returns `None` if a field is missing or malformed, except for the perfect clears, which
+    /// older summaries did not record and default to zero.
+    pub fn decode(text: &str) -> Option<Summary> {
+        let field = |key: &str| {
+            text.lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+        };
+        let mut line_clears = [0; 4];
+        let mut counts = field("line_clears")?.split(',');
+        for count in &mut line_clears {
+            *count = counts.next()?.parse().ok()?;
+        }
+        let mut board = [[None; Grid::WIDTH as usize]; Grid::HEIGHT as usize];
+        let mut rows = text.lines().filter_map(|line| line.strip_prefix("board="));
+        for row in &mut board {
+            let letters = rows.next()?;
+            if letters.chars().count() != row.len() {
+                return None;
+            }
+            for (cell, letter) in row.iter_mut().zip(letters.chars()) {
+                *cell = Tetromino::from_char(letter);
+            }
+        }
+        Some(Summary {
+            score: field("score")?.parse().ok()?,
+            lines: field("lines")?.parse().ok()?,
+            line_clears,
+            perfect_clears: field("perfect_clears").map_or(Some(0), |n| n.parse().ok())?,
+            level: field("level")?.parse().ok()?,
+            pieces: field("pieces")?.parse().ok()?,
+            duration: Duration::from_millis(field("duration_ms")?.parse().ok()?),
+            seed: field("seed")?.parse().ok()?,
+            mode: field("mode")?.parse().ok()?,
+            board,
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use rand::distributions;
+
+/// The seven one-sided tetrominoes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Tetromino {
+    I,
+    O,
+    T,
+    J,
+    L,
+    S,
+    Z,
+}
+
+impl Tetromino {
+    /// Every piece, in declaration order.
+    pub const ALL: [Tetromino; 7] = [
+        Tetromino::I,
+        Tetromino::O,
+        Tetromino::T,
+        Tetromino::J,
+        Tetromino::L,
+        Tetromino::S,
+        Tetromino::Z,
+    ];
+
+    /// The letter the piece is named after.
+    pub const fn to_char(self) -> char {
+        match self {
+            Tetromino::I => 'I',
+            Tetromino::O => 'O',
+            Tetromino::T => 'T',
+            Tetromino::J => 'J',
+            Tetromino::L => 'L',
+            Tetromino::S => 'S',
+            Tetromino::Z => 'Z',
+        }
+    }
+
+    /// The piece named after `letter`, as returned by [`Tetromino::to_char`].
+    pub const fn from_char(letter: char) -> Option<Tetromino> {
+        match letter {
+            'I' => Some(Tetromino::I),
+            'O' => Some(Tetromino::O),
+            'T' => Some(Tetromino::T),
+            'J' => Some(Tetromino::J),
+            'L' => Some(Tetromino::L),
+            'S' => Some(Tetromino::S),
+            'Z' => Some(Tetromino::Z),
+            _ => None,
+        }
+    }
+
+    /// Offsets of the four minos relative to the piece position for the given rotation.
+    ///
+    /// The shapes follow the guideline spawn orientations and rotate about the SRS centres, so
+    /// that the kick tables of [`RotationSystem::Srs`](crate::RotationSystem::Srs) apply as is.
+    pub const fn neighbors(self, rot: Rotation) -> [(i8, i8); 4] {
+        use Rotation::{DEG0, DEG180, DEG270, DEG90};
+        match (self, rot) {
+            (Tetromino::I, DEG0) => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+            (Tetromino::I, DEG90) => [(1, -1), (1, 0), (1, 1), (1, 2)],
+            (Tetromino::I, DEG180) => [(-1, 1), (0, 1), (1, 1), (2, 1)],
+            (Tetromino::I, DEG270) => [(0, -1), (0, 0), (0, 1), (0, 2)],
+            (Tetromino::O, _) => [(0, -1), (1, -1), (0, 0), (1, 0)],
+            (Tetromino::T, DEG0) => [(0, -1), (-1, 0), (0, 0), (1, 0)],
+            (Tetromino::T, DEG90) => [(0, -1), (0, 0), (1, 0), (0, 1)],
+            (Tetromino::T, DEG180) => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+            (Tetromino::T, DEG270) => [(0, -1), (-1, 0), (0, 0), (0, 1)],
+            (Tetromino::J, DEG0) => [(-1, -1), (-1, 0), (0, 0), (1, 0)],
+            (Tetromino::J, DEG90) => [(0, -1), (1, -1), (0, 0), (0, 1)],
+            (Tetromino::J, DEG180) => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+            (Tetromino::J, DEG270) => [(0, -1), (0, 0), (-1, 1), (0, 1)],
+            (Tetromino::L, DEG0) => [(1, -1), (-1, 0), (0, 0), (1, 0)],
+            (Tetromino::L, DEG90) => [(0, -1), (0, 0), (0, 1), (1, 1)],
+            (Tetromino::L, DEG180) => [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+            (Tetromino::L, DEG270) => [(-1, -1), (0, -1), (0, 0), (0, 1)],
+            (Tetromino::S, DEG0) => [(0, -1), (1, -1), (-1, 0), (0, 0)],
+            (Tetromino::S, DEG90) => [(0, -1), (0, 0), (1, 0), (1, 1)],
+            (Tetromino::S, DEG180) => [(0, 0), (1, 0), (-1, 1), (0, 1)],
+            (Tetromino::S, DEG270) => [(-1, -1), (-1, 0), (0, 0), (0, 1)],
+            (Tetromino::Z, DEG0) => [(-1, -1), (0, -1), (0, 0), (1, 0)],
+            (Tetromino::Z, DEG90) => [(1, -1), (0, 0), (1, 0), (0, 1)],
+            (Tetromino::Z, DEG180) => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+            (Tetromino::Z, DEG270) => [(0, -1), (-1, 0), (0, 0), (-1, 1)],
+        }
+    }
+}
+
+impl distributions::Distribution<Tetromino> for distributions::Standard {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Tetromino {
+        let variant: u8 = rng.gen_range(0..=Tetromino::Z as u8);
+        // SAFETY: the line above restricts the range of the random number generator to the number of variants in `Tetromino` enum.
+        unsafe { std::mem::transmute(variant) }
+    }
+}
+
+/// Orientation of a piece, measured clockwise from its spawn orientation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    DEG0,
+    DEG90,
+    DEG180,
+    DEG270,
+}
+
+impl Rotation {
+    /// The orientation after a clockwise quarter turn.
+    pub const fn spin_cw(self) -> Rotation {
+        match self {
+            Rotation::DEG0 => Rotation::DEG90,
+            Rotation::DEG90 => Rotation::DEG180,
+            Rotation::DEG180 => Rotation::DEG270,
+            Rotation::DEG270 => Rotation::DEG0,
+        }
+    }
+
+    /// The orientation after an anticlockwise quarter turn.
+    pub const fn spin_acw(self) -> Rotation {
+        match self {
+            Rotation::DEG0 => Rotation::DEG270,
+            Rotation::DEG90 => Rotation::DEG0,
+            Rotation::DEG180 => Rotation::DEG90,
+            Rotation::DEG270 => Rotation::DEG180,
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use std::path::Path;
+use std::time::{Instant, SystemTime};
+
+use macroquad::color::{colors, Color};
+use macroquad::input::{self, KeyCode};
+use macroquad::shapes;
+use macroquad::text;
+use macroquad::window;
+
+use crate::{
+    run_bot, Action, BuildLock, Changes, Controller, Game, Grid, InputFrame, Player, Replay,
+    Settings, State, Summary, Tetromino,
+};
+
+const GRID_CELL_SIZE: f32 = 32.;
+const MARGIN: f32 = 20.;
+const PIECE_PREVIEW_WIDTH: f32 = GRID_CELL_SIZE * 5.0;
+const SCREEN_WIDTH: f32 =
+    Grid::WIDTH as f32 * GRID_CELL_SIZE + MARGIN * 2.0 + PIECE_PREVIEW_WIDTH + MARGIN;
+const SCREEN_HEIGHT: f32 = MARGIN + Grid::HEIGHT as f32 * GRID_CELL_SIZE + MARGIN;
+
+/// Frames an announcement, such as a T-spin, stays on the HUD.
+const ANNOUNCEMENT_FRAMES: u32 = 90;
+
+const BORDER_COLOR: Color = colors::BLACK;
+const BACKGROUND_COLOR: Color = Color::new(0.125, 0.1484375, 0.2265625, 1.);
+
+/// The color a locked or falling mino of the piece is painted with.
+const fn fill_color(tetromino: Tetromino) -> Color {
+    match tetromino {
+        Tetromino::I => Color::new(0., 1.0, 1., 1.),
+        Tetromino::O => Color::new(1., 1.0, 0., 1.),
+        Tetromino::T => Color::new(1., 0.0, 1., 1.),
+        Tetromino::J => Color::new(0., 0.0, 1., 1.),
+        Tetromino::L => Color::new(1., 0.5, 0., 1.),
+        Tetromino::S => Color::new(0., 1.0, 0., 1.),
+        Tetromino::Z => Color::new(1., 0.0, 0., 1.),
+    }
+}
+
+/// The translucent color of the ghost piece.
+const fn ghost_color(tetromino: Tetromino) -> Color {
+    let mut color = fill_color(tetromino);
+    color.a = 0.3;
+    color
+}
+
+const KEY_BINDINGS: [(Action, &[KeyCode]); 10] = [
+    (Action::Left, &[KeyCode::Left]),
+    (Action::Right, &[KeyCode::Right]),
+    (Action::SoftDrop, &[KeyCode::Down]),
+    (Action::HardDrop, &[KeyCode::Space]),
+    (Action::RotateCw, &[KeyCode::Up, KeyCode::X]),
+    (
+        Action::RotateCcw,
+        &[KeyCode::LeftControl, KeyCode::RightControl, KeyCode::Z],
+    ),
+    (
+        Action::Hold,
+        &[KeyCode::LeftShift, KeyCode::RightShift, KeyCode::C],
+    ),
+    (Action::Pause, &[KeyCode::Escape]),
+    (Action::Confirm, &[KeyCode::Enter]),
+    (Action::Quit, &[KeyCode::Q]),
+];
+
+/// The player at the keyboard, reading the keys of the window opened by [`run_tetris`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keyboard;
+
+impl Controller for Keyboard {
+    /// Translates the keyboard state of the current frame into an [`InputFrame`].
+    fn input(&mut self, _game: &Game) -> Option<InputFrame> {
+        let mut frame = InputFrame::default();
+        for (action, key_codes) in KEY_BINDINGS {
+            if key_codes.iter().any(|&k| input::is_key_pressed(k)) {
+                frame.pressed.insert(action);
+            }
+            if key_codes.iter().any(|&k| input::is_key_down(k)) {
+                frame.held.insert(action);
+            }
+        }
+        Some(frame)
+    }
+}
+
+/// A feat of a locked piece, such as a T-spin or a perfect clear, shown on the HUD for a while.
+struct Announcement {
+    title: String,
+    detail: &'static str,
+    frames_left: u32,
+}
+
+impl Announcement {
+    /// Announces what the piece locked by `changes` achieved, if anything worth it.
+    fn of(changes: &Changes) -> Option<Announcement> {
+        let lines = match changes.lines_cleared {
+            0 => "",
+            1 => "SINGLE",
+            2 => "DOUBLE",
+            3 => "TRIPLE",
+            _ => "TETRIS",
+        };
+        let (title, detail) = if changes.perfect_clear {
+            ("PERFECT CLEAR".to_owned(), lines)
+        } else {
+            let t_spin = changes.t_spin?;
+            (t_spin.name().to_uppercase(), lines)
+        };
+        Some(Announcement {
+            title,
+            detail,
+            frames_left: ANNOUNCEMENT_FRAMES,
+        })
+    }
+
+    /// Counts one more frame on screen, until the announcement disappears.
+    fn fade(mut self) -> Option<Announcement> {
+        self.frames_left = self.frames_left.checked_sub(1)?;
+        Some(self)
+    }
+}
+
+fn draw(game: &Game, announcement: Option<&Announcement>) {
+    window::clear_background(BORDER_COLOR);
+
+    draw_grid(&game.grid);
+
+    let neighbors = game.tetromino.neighbors(game.rot);
+    let mut ghost_offset = 0;
+    while game._movable_with(game.rot, 0, ghost_offset + 1) {
+        ghost_offset += 1;
+    }
+    draw_tetromino(game.pos, game.tetromino, neighbors, ghost_offset);
+
+    if game.state != State::Play {
+        draw_overlay(game.state);
+    }
+
+    let x_right_bar: f32 = MARGIN + (f32::from(Grid::WIDTH) * GRID_CELL_SIZE) + MARGIN;
+    let y_score = MARGIN + GRID_CELL_SIZE;
+    let score = game.score.to_string();
+    let y_hold = draw_stat("Score:", &score, 20., (x_right_bar, y_score));
+    let y_next = draw_tetromino_box(game.holding_tetromino, (x_right_bar, y_hold));
+    let y_next = y_next + GRID_CELL_SIZE * f32::from(Grid::HEIGHT / 4);
+    let y_seed = draw_tetromino_box(Some(game.next_tetromino), (x_right_bar, y_next));
+    // a seed may have up to 20 digits, which only fits the bar with a smaller font
+    let seed = game.seed().to_string();
+    let y_announcement = draw_stat("Seed:", &seed, 16., (x_right_bar, y_seed + MARGIN));
+    if let Some(announcement) = announcement {
+        let Announcement { title, detail, .. } = announcement;
+        let _ = draw_stat(title, detail, 20., (x_right_bar, y_announcement + MARGIN));
+    }
+
+    fn draw_grid(grid: &Grid) {
+        let [x_base, y_base] = [MARGIN; 2];
+        let [w, h] = [
+            Grid::WIDTH as f32 * GRID_CELL_SIZE + MARGIN,
+            Grid::HEIGHT as f32 * GRID_CELL_SIZE + MARGIN,
+        ];
+        let [x, y] = [x_base - MARGIN / 2., y_base - MARGIN / 2.];
+        shapes::draw_rectangle_lines(x, y, w, h, MARGIN, BACKGROUND_COLOR);
+        for y in 0..Grid::HEIGHT {
+            for x in 0..Grid::WIDTH {
+                let cell = grid.at(x, y);
+                let [w, h] = [GRID_CELL_SIZE; 2];
+                let [x, y] = [x_base + w * x as f32, y_base + h * y as f32];
+                let color = cell.map(fill_color).unwrap_or(BORDER_COLOR);
+                shapes::draw_rectangle(x, y, w, h, color);
+            }
+        }
+    }
+
+    fn draw_tetromino(
+        (x, y): (i8, i8),
+        tetromino: Tetromino,
+        neighbors: [(i8, i8); 4],
+        ghost_offset: i8,
+    ) {
+        let [x_base, y_base] = [MARGIN; 2];
+        for (dx, dy) in neighbors {
+            let [w, h] = [GRID_CELL_SIZE; 2];
+            let x = x_base + w * f32::from(x + dx);
+            let y_orig = y_base + h * f32::from(y + dy);
+            shapes::draw_rectangle(x, y_orig, w, h, fill_color(tetromino));
+            if ghost_offset != 0 {
+                let y_ghost = y_base + h * f32::from(y + dy + ghost_offset);
+                shapes::draw_rectangle(x, y_ghost, w, h, ghost_color(tetromino));
+            }
+        }
+    }
+
+    fn draw_overlay(state: State) {
+        let [x, y] = [MARGIN; 2];
+        let (w, h) = (
+            GRID_CELL_SIZE * f32::from(Grid::WIDTH),
+            GRID_CELL_SIZE * f32::from(Grid::HEIGHT),
+        );
+        shapes::draw_rectangle(x, y, w, h, Color::new(0., 0., 0., 0.75));
+
+        let [base_x, base_y] = [GRID_CELL_SIZE * 2.5, SCREEN_HEIGHT / 2.];
+        const SIZE_TITLE: f32 = 50.;
+        const SIZE_DESC: f32 = 20.;
+        const COLOR_TITLE: Color = colors::WHITE;
+        const COLOR_DESC: Color = colors::LIGHTGRAY;
+        if state == State::Pause {
+            let [x, y] = [base_x + GRID_CELL_SIZE, base_y - 50.];
+            text::draw_text("PAUSED", x, y, SIZE_TITLE, COLOR_TITLE);
+            let msg = "Press Enter to unpause";
+            text::draw_text(msg, base_x, base_y, SIZE_DESC, COLOR_DESC);
+        } else if state == State::Over {
+            let [x, y] = [base_x, base_y - 50.];
+            text::draw_text("GAME OVER", x, y, SIZE_TITLE, COLOR_TITLE);
+            let msg = "Press ENTER to restart";
+            text::draw_text(msg, base_x, base_y, SIZE_DESC, COLOR_DESC);
+        } else if state == State::Start {
+            let [x, y] = [base_x + GRID_CELL_SIZE, base_y - 50.];
+            text::draw_text("TETRIS", x, y, SIZE_TITLE, COLOR_TITLE);
+            let msg = "Press ENTER to start";
+            let [x, y] = [base_x + GRID_CELL_SIZE / 2., base_y];
+            text::draw_text(msg, x, y, SIZE_DESC, COLOR_DESC);
+            let [x, y] = [base_x + GRID_CELL_SIZE, base_y + 50.];
+            text::draw_text("Press Q to quit", x, y, SIZE_DESC, COLOR_DESC);
+        }
+    }
+
+    fn draw_stat(label: &str, value: &str, size: f32, (x_base, y_base): (f32, f32)) -> f32 {
+        text::draw_text(label, x_base, y_base, 20., colors::LIGHTGRAY);
+        let [x, y] = [x_base, y_base + MARGIN];
+        text::draw_text(value, x, y, size, colors::LIGHTGRAY);
+        y_base + MARGIN * 2.
+    }
+
+    fn draw_tetromino_box(tetromino: Option<Tetromino>, (x_base, y_base): (f32, f32)) -> f32 {
+        const BOX_MARGIN: f32 = GRID_CELL_SIZE + MARGIN;
+        let [w, h] = [
+            GRID_CELL_SIZE * 2. + BOX_MARGIN * 2.,
+            GRID_CELL_SIZE * 1. + BOX_MARGIN * 2.,
+        ];
+        shapes::draw_rectangle(x_base, y_base, w, h, BACKGROUND_COLOR);
+
+        if let Some(tetromino) = tetromino {
+            let neighbors = tetromino.neighbors(Default::default());
+            let [w_cell, h_cell] = [GRID_CELL_SIZE; 2];
+            let (dx_min, dx_max) = neighbors
+                .iter()
+                .fold((i8::MAX, i8::MIN), |(lo, hi), &(dx, _)| {
+                    (lo.min(dx), hi.max(dx))
+                });
+            let (dy_min, dy_max) = neighbors
+                .iter()
+                .fold((i8::MAX, i8::MIN), |(lo, hi), &(_, dy)| {
+                    (lo.min(dy), hi.max(dy))
+                });
+            // centre the bounding box of the piece inside the box
+            let x_center = x_base + w / 2. - w_cell * f32::from(dx_min + dx_max + 1) / 2.;
+            let y_center = y_base + h / 2. - h_cell * f32::from(dy_min + dy_max + 1) / 2.;
+            for (dx, dy) in neighbors {
+                let [x, y] = [
+                    x_center + w_cell * f32::from(dx),
+                    y_center + h_cell * f32::from(dy),
+                ];
+                shapes::draw_rectangle(x, y, w_cell, h_cell, fill_color(tetromino));
+            }
+        }
+        y_base + h + MARGIN
+    }
+}
+
+/// Opens a window, lets the developer play until they quit and returns the final score.
+///
+/// Returns `0` if the window could not produce a result.
+pub fn run_tetris() -> u32 {
+    run_tetris_with(Settings::default()).score
+}
+
+/// Same as [`run_tetris`], but plays with the given `settings` and returns the whole [`Summary`]
+/// of the last game.
+///
+/// Returns the summary of a game that never started if the window could not produce a result.
+pub fn run_tetris_with(settings: Settings) -> Summary {
+    run_tetris_recorded(settings).0
+}
+
+/// Same as [`run_tetris_with`], but also returns the [`Replay`] of the last game, which
+/// [`Replay::play`] brings to the same summary.
+///
+/// When `settings` pick the [`Player::Bot`], no window is opened: see [`run_bot`].
+pub fn run_tetris_recorded(settings: Settings) -> (Summary, Replay) {
+    if settings.player == Player::Bot {
+        return run_bot(settings);
+    }
+
+    use std::sync::Mutex;
+    // The window may be opened several times by the same process, e.g. by a proc-macro server
+    // expanding `run!` again, so the slot is emptied after each session.
+    static SESSION_CELL: Mutex<Option<(Summary, Replay)>> = Mutex::new(None);
+
+    macroquad::Window::new("buildtime_tetris", async move {
+        let mut game = Game::with_settings(settings);
+        let mut replay = Replay::new(&game);
+        let deadline = settings.timeout.map(|timeout| Instant::now() + timeout);
+        let mut announcement = None;
+        window::request_new_screen_size(SCREEN_WIDTH, SCREEN_HEIGHT);
+        while game.state != State::WindowClose {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline)
+                || settings
+                    .piece_limit
+                    .is_some_and(|limit| game.pieces() >= limit)
+            {
+                break;
+            }
+            let Some(input) = Keyboard.input(&game) else {
+                break;
+            };
+            let changes = replay.update(&mut game, input);
+            announcement =
+                Announcement::of(&changes).or_else(|| announcement.and_then(Announcement::fade));
+            draw(&game, announcement.as_ref());
+            window::next_frame().await
+        }
+
+        *SESSION_CELL.lock().unwrap() = Some((Summary::of(&game), replay));
+    });
+
+    SESSION_CELL.lock().unwrap().take().unwrap_or_else(|| {
+        let game = Game::with_settings(settings);
+        (Summary::of(&game), Replay::new(&game))
+    })
+}
+
+/// Same as [`run_tetris_with`], but plays at most once per `cargo build`.
+///
+/// Every build script and procedural macro of the build calling it waits for the session of
+/// the first one, through a [`BuildLock`], and returns its summary, whatever `settings` they
+/// asked for. Falls back to playing alone if the lock cannot be taken.
+///
+/// The process that plays saves the replay of the last game into `replay_dir`, named after the
+/// time it ended and its score.
+pub fn run_tetris_shared(settings: Settings, replay_dir: &Path) -> Summary {
+    let lock = BuildLock::acquire(|| {
+        eprintln!("Waiting for the Tetris session of another crate in this build to end");
+    });
+    let lock = match lock {
+        Ok(lock) => Some(lock),
+        Err(err) => {
+            eprintln!("warning: failed to lock the Tetris session, playing alone: {err}");
+            None
+        }
+    };
+    if let Some(summary) = lock.as_ref().and_then(BuildLock::result) {
+        return summary;
+    }
+
+    let (summary, replay) = run_tetris_recorded(settings);
+    let ended = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let replay_path = replay_dir.join(format!(
+        "{ended}-score-{}.{}",
+        summary.score,
+        Replay::EXTENSION
+    ));
+    if let Err(err) = replay.save(&replay_path) {
+        eprintln!(
+            "warning: failed to save the replay to {}: {err}",
+            replay_path.display()
+        );
+    }
+    if let Some(Err(err)) = lock.map(|lock| lock.record(&summary)) {
+        eprintln!("warning: failed to share the Tetris session with the rest of the build: {err}");
+    }
+    summary
+}